@@ -0,0 +1,25 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use cbor_debug::{decode, decode_single, encode, MajorType};
+
+/// An array of a million small integers, each a single byte.
+fn small_integers() -> Vec<u8> {
+    encode(&MajorType::Arr((0..1_000_000).map(|i| MajorType::U(i % 24)).collect())).unwrap()
+}
+
+/// An array of a million integers of up to five bytes, mixed signs.
+fn mixed_integers() -> Vec<u8> {
+    let items = (0..1_000_000_u64).map(|i| if i % 2 == 0 { MajorType::U(i) } else { MajorType::N(-(i as i128)) });
+    encode(&MajorType::Arr(items.collect())).unwrap()
+}
+
+fn integer_arrays(c: &mut Criterion) {
+    let small = small_integers();
+    let mixed = mixed_integers();
+    c.bench_function("decode_single small integers", |b| b.iter(|| decode_single(black_box(&small)).unwrap()));
+    c.bench_function("decode_single mixed integers", |b| b.iter(|| decode_single(black_box(&mixed)).unwrap()));
+    c.bench_function("decode small integers", |b| b.iter(|| decode(black_box(&small))));
+}
+
+criterion_group!(benches, integer_arrays);
+criterion_main!(benches);
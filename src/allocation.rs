@@ -0,0 +1,41 @@
+use crate::{decode_item, DecodeError, MajorType, State};
+
+/// Decodes all top-level items, calling `hook` with the size in bytes before every allocation
+/// made for the result: the contents of strings and the storage of arrays, maps and the returned
+/// list. If `hook` returns `false`, decoding stops with [`DecodeError::AllocationDenied`].
+pub fn decode_with_allocation_hook(bytes: &[u8], mut hook: impl FnMut(usize) -> bool) -> Result<Vec<MajorType>, DecodeError> {
+    let mut state = State { allocation_hook: Some(&mut hook), ..Default::default() };
+    let mut items = Vec::new();
+    let mut idx = 0;
+    while idx < bytes.len() {
+        let start = idx;
+        let item = decode_item(bytes, &mut idx, &mut state)?;
+        state.grow(&mut items, start)?;
+        items.push(item);
+    }
+    Ok(items)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::MajorType::*;
+
+    #[test]
+    fn deny_over_threshold() {
+        // [h'0102', h'00 * 2000']
+        let mut bytes = vec![0x82, 0x42, 0x01, 0x02, 0x59, 0x07, 0xd0];
+        bytes.extend([0; 2000]);
+        let mut requested = Vec::new();
+        let result = decode_with_allocation_hook(&bytes, |size| {
+            requested.push(size);
+            size <= 1024
+        });
+        assert_eq!(Err(DecodeError::AllocationDenied { offset: 4, size: 2000 }), result);
+        // the array made room for four items before its second one was decoded
+        assert_eq!(vec![2, 4 * std::mem::size_of::<MajorType>(), 2000], requested);
+
+        let items = decode_with_allocation_hook(&bytes[1..4], |size| size <= 1024).unwrap();
+        assert_eq!(vec![BStr(vec![1, 2])], items);
+    }
+}
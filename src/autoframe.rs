@@ -0,0 +1,125 @@
+use crate::MajorType::{self, *};
+use crate::{decode_at, decode_at_offset};
+
+/// How an item found by [`decode_autoframe`] was delimited.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Framing {
+    /// The item was self-delimited CBOR.
+    Bare,
+    /// The item was preceded by its length as a big-endian `u16`.
+    U16Prefix,
+    /// The item was preceded by its length as a big-endian `u32`.
+    U32Prefix,
+}
+
+/// Which framing [`decode_autoframe_with_order`] tries first at each item.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum FramingOrder {
+    /// Length prefixes first, then self-delimited CBOR. A bare item whose first bytes happen to
+    /// look like a prefix framing exactly one well-formed item is misread.
+    #[default]
+    PrefixFirst,
+    /// Self-delimited CBOR first, and length prefixes only where that fails. Prefixes are then
+    /// rarely found, as their bytes usually decode as small integers, e.g. `00 00 00 03` as four.
+    BareFirst,
+}
+
+/// Decodes a buffer in which bare items and length-prefixed items may be interleaved, reporting
+/// the framing of each item, trying length prefixes first. Ends with a `Bare` `Invalid` item if
+/// the rest cannot be decoded.
+///
+/// This is a heuristic and can guess wrong, see [`FramingOrder`]. Prefixes are tried first
+/// because the bytes of a prefix usually also decode as small integers.
+pub fn decode_autoframe(bytes: &[u8]) -> Vec<(Framing, MajorType)> {
+    decode_autoframe_with_order(bytes, FramingOrder::PrefixFirst)
+}
+
+/// Like [`decode_autoframe`], trying the framings in the given `order`.
+pub fn decode_autoframe_with_order(bytes: &[u8], order: FramingOrder) -> Vec<(Framing, MajorType)> {
+    let mut output = Vec::new();
+    let mut idx = 0;
+    while idx < bytes.len() {
+        if order == FramingOrder::PrefixFirst {
+            if let Some((framing, item, end)) = prefixed(bytes, idx) {
+                output.push((framing, item));
+                idx = end;
+                continue;
+            }
+        }
+        let start = idx;
+        match decode_at(bytes, &mut idx) {
+            Ok(item) => output.push((Framing::Bare, item)),
+            Err(_) => match prefixed(bytes, start).filter(|_| order == FramingOrder::BareFirst) {
+                Some((framing, item, end)) => {
+                    output.push((framing, item));
+                    idx = end;
+                }
+                None => {
+                    output.push((Framing::Bare, Invalid));
+                    break;
+                }
+            },
+        }
+    }
+    output
+}
+
+/// Tries the length prefixes at `idx`, returning the item that one of them frames with the offset past it.
+fn prefixed(bytes: &[u8], idx: usize) -> Option<(Framing, MajorType, usize)> {
+    for (framing, width) in [(Framing::U32Prefix, 4), (Framing::U16Prefix, 2)] {
+        let Some(prefix) = bytes.get(idx..idx + width) else { continue };
+        let len = prefix.iter().fold(0_usize, |acc, &b| (acc << 8) | b as usize);
+        let start = idx + width;
+        if len == 0 || start + len > bytes.len() {
+            continue;
+        }
+        if let Ok((item, end)) = decode_at_offset(&bytes[..start + len], start) {
+            if end == start + len {
+                return Some((framing, item, end));
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn bare_and_u32_framed() {
+        // [1, 2], then "hi" after a u32 length prefix, then 7 after a u16 one
+        let bytes = [0x82, 0x01, 0x02, 0x00, 0x00, 0x00, 0x03, 0x62, b'h', b'i', 0x00, 0x01, 0x07];
+        assert_eq!(vec![(Framing::Bare, Arr(vec![U(1), U(2)])), (Framing::U32Prefix, Str("hi".into())), (Framing::U16Prefix, U(7))],
+                   decode_autoframe(&bytes));
+    }
+
+    #[test]
+    fn bare_item_misread_as_prefixed() {
+        // 0, 1, 7, of which the first two look like a u16 length prefix
+        let bytes = [0x00, 0x01, 0x07];
+        assert_eq!(vec![(Framing::U16Prefix, U(7))], decode_autoframe(&bytes));
+        assert_eq!(vec![(Framing::Bare, U(0)), (Framing::Bare, U(1)), (Framing::Bare, U(7))],
+                   decode_autoframe_with_order(&bytes, FramingOrder::BareFirst));
+    }
+
+    #[test]
+    fn bare_first() {
+        // 1, then a byte string of 7165 bytes after a u16 length prefix of 0x1c00, which does not
+        // decode as a bare item
+        let mut bytes = vec![0x01, 0x1c, 0x00, 0x59, 0x1b, 0xfd];
+        bytes.extend([0; 7165]);
+        assert_eq!(vec![(Framing::Bare, U(1)), (Framing::U16Prefix, BStr(vec![0; 7165]))],
+                   decode_autoframe_with_order(&bytes, FramingOrder::BareFirst));
+        // [1, 2], then "hi" after a u32 length prefix, whose bytes decode as bare integers
+        let bytes = [0x82, 0x01, 0x02, 0x00, 0x00, 0x00, 0x03, 0x62, b'h', b'i'];
+        assert_eq!(vec![(Framing::Bare, Arr(vec![U(1), U(2)])), (Framing::Bare, U(0)), (Framing::Bare, U(0)),
+                        (Framing::Bare, U(0)), (Framing::Bare, U(3)), (Framing::Bare, Str("hi".into()))],
+                   decode_autoframe_with_order(&bytes, FramingOrder::BareFirst));
+    }
+
+    #[test]
+    fn undecodable_rest() {
+        assert_eq!(vec![(Framing::Bare, U(1)), (Framing::Bare, Invalid)], decode_autoframe(&[0x01, 0x82, 0x01]));
+    }
+}
@@ -0,0 +1,88 @@
+const STANDARD: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const URL_SAFE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// Base64 (RFC 4648 section 4) with padding.
+pub(crate) fn encode_standard(bytes: &[u8]) -> String {
+    encode_with(bytes, STANDARD, true)
+}
+
+/// URL-safe base64 (RFC 4648 section 5) without padding, as RFC 8949 uses for byte strings in JSON.
+pub(crate) fn encode_url(bytes: &[u8]) -> String {
+    encode_with(bytes, URL_SAFE, false)
+}
+
+/// Decodes URL-safe base64, with or without padding.
+pub(crate) fn decode_url(text: &str) -> Option<Vec<u8>> {
+    decode_with(text, URL_SAFE)
+}
+
+/// Decodes base64, with or without padding.
+pub(crate) fn decode_standard(text: &str) -> Option<Vec<u8>> {
+    decode_with(text, STANDARD)
+}
+
+fn decode_with(text: &str, alphabet: &[u8; 64]) -> Option<Vec<u8>> {
+    let text = text.as_bytes();
+    let text = text.strip_suffix(b"==").or_else(|| text.strip_suffix(b"=")).unwrap_or(text);
+    if text.len() % 4 == 1 {
+        return None;
+    }
+    let mut out = Vec::new();
+    for chunk in text.chunks(4) {
+        let n = chunk.iter().enumerate().try_fold(0_u32, |n, (i, c)| {
+            let value = alphabet.iter().position(|a| a == c)? as u32;
+            Some(n | value << (18 - 6 * i))
+        })?;
+        out.extend(&n.to_be_bytes()[1..chunk.len()]);
+    }
+    Some(out)
+}
+
+fn encode_with(bytes: &[u8], alphabet: &[u8; 64], pad: bool) -> String {
+    let mut out = String::new();
+    for chunk in bytes.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0_u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..=chunk.len() {
+            out.push(alphabet[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+        }
+        if pad {
+            out.extend(std::iter::repeat_n('=', 3 - chunk.len()));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn rfc4648_vectors() {
+        for (input, expected) in [("", ""), ("f", "Zg"), ("fo", "Zm8"), ("foo", "Zm9v"), ("foob", "Zm9vYg"),
+            ("fooba", "Zm9vYmE"), ("foobar", "Zm9vYmFy")] {
+            assert_eq!(expected, encode_url(input.as_bytes()));
+        }
+    }
+
+    #[test]
+    fn url_safe() {
+        assert_eq!("-_8", encode_url(&[0xfb, 0xff]));
+    }
+
+    #[test]
+    fn decode_round_trip() {
+        for input in ["", "f", "fo", "foo", "foob", "fooba", "foobar"] {
+            assert_eq!(Some(input.as_bytes().to_vec()), decode_url(&encode_url(input.as_bytes())));
+            assert_eq!(Some(input.as_bytes().to_vec()), decode_standard(&encode_standard(input.as_bytes())));
+        }
+        assert_eq!(Some(vec![0xfb, 0xff]), decode_standard("+/8"));
+        assert_eq!(None, decode_url("+/8="));
+        assert_eq!(None, decode_url("Zm9vY"));
+    }
+
+    #[test]
+    fn standard_padded() {
+        assert_eq!("+/8=", encode_standard(&[0xfb, 0xff]));
+        assert_eq!("Zm9vYg==", encode_standard(b"foob"));
+    }
+}
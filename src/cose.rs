@@ -0,0 +1,210 @@
+use std::collections::HashMap;
+
+use crate::MajorType::{self, *};
+use crate::{decode_document, decode_single, get_len, DecodeError, Path, Segment};
+
+/// A COSE message or one of the signatures or recipients nested in it (RFC 9052): its name and its
+/// fields with their names.
+pub(crate) struct CoseStructure<'a> {
+    pub name: &'static str,
+    pub fields: Vec<(&'static str, &'a MajorType)>,
+}
+
+/// The COSE message under tag `number`, if the tag is one of a COSE message and `content` is laid
+/// out as one.
+pub(crate) fn cose_message(number: u64, content: &MajorType) -> Option<CoseStructure<'_>> {
+    let (name, fields): (_, &[_]) = match number {
+        16 => ("CoseEncrypt0", &["protected", "unprotected", "ciphertext"]),
+        17 => ("CoseMac0", &["protected", "unprotected", "payload", "tag"]),
+        18 => ("CoseSign1", &["protected", "unprotected", "payload", "signature"]),
+        96 => ("CoseEncrypt", &["protected", "unprotected", "ciphertext", "recipients"]),
+        97 => ("CoseMac", &["protected", "unprotected", "payload", "tag", "recipients"]),
+        98 => ("CoseSign", &["protected", "unprotected", "payload", "signatures"]),
+        _ => return None,
+    };
+    structure(name, fields, content)
+}
+
+/// The COSE structure `content` is laid out as, if it is: a signature when `field` is
+/// `"signatures"`, a recipient when it is `"recipients"`.
+pub(crate) fn cose_nested<'a>(field: &str, content: &'a MajorType) -> Option<CoseStructure<'a>> {
+    match field {
+        "signatures" => structure("CoseSignature", &["protected", "unprotected", "signature"], content),
+        "recipients" => structure("CoseRecipient", &["protected", "unprotected", "ciphertext", "recipients"], content),
+        _ => None,
+    }
+}
+
+fn structure<'a>(name: &'static str, fields: &'static [&'static str], content: &'a MajorType) -> Option<CoseStructure<'a>> {
+    let Arr(items) = content else { return None };
+    // recipients leave out recipients of their own when they have none
+    let fields = match fields.split_last() {
+        Some((&"recipients", rest)) if items.len() == rest.len() => rest,
+        _ => fields,
+    };
+    (items.len() == fields.len()).then(|| CoseStructure { name, fields: fields.iter().copied().zip(items).collect() })
+}
+
+/// The header map encoded in the byte string of protected headers, the empty string standing for an
+/// empty map.
+pub(crate) fn protected_headers(value: &MajorType) -> Option<MajorType> {
+    match value {
+        BStr(bytes) if bytes.is_empty() => Some(Map(vec![])),
+        BStr(bytes) => decode_single(bytes).ok().filter(|headers| matches!(headers, Map(_))),
+        _ => None,
+    }
+}
+
+/// The content of the protected headers byte string of the COSE message in `bytes`, exactly as it
+/// was encoded, since signatures and MACs are computed over those bytes and not over a re-encoding.
+/// The message must be a single item: an array, tagged or not, whose first element is a
+/// definite-length byte string. Otherwise this fails with [`DecodeError::TypeMismatch`].
+pub fn cose_protected_raw(bytes: &[u8]) -> Result<&[u8], DecodeError> {
+    let document = decode_document(bytes)?;
+    let mut message = document.root();
+    while let Tag(_, content) = message {
+        message = content;
+    }
+    let mismatch = |offset, expected, found| DecodeError::TypeMismatch { offset, expected, found };
+    let first = match message {
+        Arr(items) if !items.is_empty() => &items[0],
+        Arr(_) => return Err(mismatch(0, "COSE message", "empty array")),
+        message => return Err(mismatch(0, "COSE message", message.type_name())),
+    };
+    let raw = document.raw_slice(&Path(vec![Segment::Index(0)])).expect("the message has a first element");
+    let offset = raw.as_ptr() as usize - bytes.as_ptr() as usize;
+    match first {
+        // the chunks of an indefinite-length string are not one slice
+        BStr(_) if raw[0] == 0x5f => Err(mismatch(offset, "byte string", "indefinite-length byte string")),
+        BStr(_) => {
+            let mut idx = 0;
+            get_len(raw, &mut idx)?;
+            Ok(&raw[idx..])
+        }
+        first => Err(mismatch(offset, "byte string", first.type_name())),
+    }
+}
+
+/// The name of an algorithm from the IANA "COSE Algorithms" registry, if `id` is a commonly used one.
+pub fn cose_algorithm_name(id: i64) -> Option<&'static str> {
+    Some(match id {
+        -65535 => "RS1",
+        -259 => "RS512",
+        -258 => "RS384",
+        -257 => "RS256",
+        -47 => "ES256K",
+        -44 => "SHA-512",
+        -43 => "SHA-384",
+        -39 => "PS512",
+        -38 => "PS384",
+        -37 => "PS256",
+        -36 => "ES512",
+        -35 => "ES384",
+        -31 => "ECDH-ES + A256KW",
+        -30 => "ECDH-ES + A192KW",
+        -29 => "ECDH-ES + A128KW",
+        -26 => "ECDH-ES + HKDF-512",
+        -25 => "ECDH-ES + HKDF-256",
+        -16 => "SHA-256",
+        -8 => "EdDSA",
+        -7 => "ES256",
+        -6 => "direct",
+        -5 => "A256KW",
+        -4 => "A192KW",
+        -3 => "A128KW",
+        1 => "A128GCM",
+        2 => "A192GCM",
+        3 => "A256GCM",
+        4 => "HMAC 256/64",
+        5 => "HMAC 256/256",
+        6 => "HMAC 384/384",
+        7 => "HMAC 512/512",
+        10 => "AES-CCM-16-64-128",
+        11 => "AES-CCM-16-64-256",
+        12 => "AES-CCM-64-64-128",
+        13 => "AES-CCM-64-64-256",
+        24 => "ChaCha20/Poly1305",
+        _ => return None,
+    })
+}
+
+/// The registered names of the CBOR Web Token claim keys (RFC 8392 section 9.1), as a dictionary for
+/// [`decode_with_dictionary`](crate::decode_with_dictionary).
+pub fn cwt_claim_names() -> HashMap<u64, String> {
+    [(1, "iss"), (2, "sub"), (3, "aud"), (4, "exp"), (5, "nbf"), (6, "iat"), (7, "cti"), (8, "cnf")]
+        .into_iter()
+        .map(|(key, name)| (key, name.to_string()))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{decode_with_dictionary, decode_with_options, encode, DecodeOptions};
+
+    fn protected(entries: Vec<(MajorType, MajorType)>) -> MajorType {
+        BStr(encode(&Map(entries)).unwrap())
+    }
+
+    #[test]
+    fn sign_with_two_signatures() {
+        let signatures = Arr(vec![
+            Arr(vec![protected(vec![(U(1), N(-7))]), Map(vec![(U(4), BStr(b"k1".to_vec()))]), BStr(vec![1, 2])]),
+            Arr(vec![protected(vec![(U(1), N(-8))]), Map(vec![]), BStr(vec![3])]),
+        ]);
+        let message = Tag(98, Box::new(Arr(vec![BStr(vec![]), Map(vec![]), BStr(b"hi".to_vec()), signatures])));
+        let options = DecodeOptions { cose_structures: true, cose_algorithm_names: true, ..Default::default() };
+        assert_eq!("[CoseSign({protected: Map({}), unprotected: Map({}), payload: BStr([104, 105]), signatures: [\
+                    CoseSignature({protected: Map({U(1): N(-7 /ES256/)}), unprotected: Map({U(4): BStr([107, 49])}), signature: BStr([1, 2])}), \
+                    CoseSignature({protected: Map({U(1): N(-8 /EdDSA/)}), unprotected: Map({}), signature: BStr([3])})]})]",
+                   decode_with_options(&encode(&message).unwrap(), &options));
+    }
+
+    #[test]
+    fn encrypt_with_nested_recipients() {
+        // a recipient that has a recipient of its own, whose protected headers are not a map
+        let inner = Arr(vec![BStr(vec![0x01]), Map(vec![]), Null]);
+        let recipient = Arr(vec![protected(vec![(U(1), N(-3))]), Map(vec![]), BStr(vec![9]), Arr(vec![inner])]);
+        let message = Tag(96, Box::new(Arr(vec![BStr(vec![]), Map(vec![]), BStr(vec![8]), Arr(vec![recipient])])));
+        let options = DecodeOptions { cose_structures: true, ..Default::default() };
+        assert_eq!("[CoseEncrypt({protected: Map({}), unprotected: Map({}), ciphertext: BStr([8]), recipients: [\
+                    CoseRecipient({protected: Map({U(1): N(-3)}), unprotected: Map({}), ciphertext: BStr([9]), recipients: [\
+                    CoseRecipient({protected: BStr([1]), unprotected: Map({}), ciphertext: Null})]})]})]",
+                   decode_with_options(&encode(&message).unwrap(), &options));
+        // not laid out as a COSE message
+        assert_eq!("[Tag(98, Arr([U(1)]))]", decode_with_options(&[0xd8, 0x62, 0x81, 0x01], &options));
+    }
+
+    #[test]
+    fn cwt_claims() {
+        // {1: "coap://as.example.com", 4: 1444064944, 99: 0}
+        let mut bytes = vec![0xa3, 0x01, 0x75];
+        bytes.extend(b"coap://as.example.com");
+        bytes.extend([0x04, 0x1a, 0x56, 0x12, 0xae, 0xb0, 0x18, 0x63, 0x00]);
+        assert_eq!("[Map({(\"iss\"=1): Str(\"coap://as.example.com\"), (\"exp\"=4): U(1444064944), U(99): U(0)})]",
+                   decode_with_dictionary(&bytes, &cwt_claim_names()));
+    }
+
+    #[test]
+    fn protected_headers_raw() {
+        // 18([<<{1: -7 in two bytes}>>, {}, h'0102', h'ff']), which would re-encode differently
+        let bytes = [0xd2, 0x84, 0x44, 0xa1, 0x01, 0x38, 0x06, 0xa0, 0x42, 0x01, 0x02, 0x41, 0xff];
+        let raw = cose_protected_raw(&bytes).unwrap();
+        assert_eq!(&bytes[3..7], raw);
+        assert!(std::ptr::eq(raw.as_ptr(), bytes[3..].as_ptr()));
+        // a length in its own byte
+        let mut long = vec![0x81, 0x58, 0x20];
+        long.extend([0; 32]);
+        assert_eq!(Ok(&long[3..]), cose_protected_raw(&long));
+    }
+
+    #[test]
+    fn protected_headers_raw_missing() {
+        let mismatch = |offset, expected, found| Err(DecodeError::TypeMismatch { offset, expected, found });
+        assert_eq!(mismatch(1, "byte string", "map"), cose_protected_raw(&[0x81, 0xa0]));
+        assert_eq!(mismatch(1, "byte string", "indefinite-length byte string"), cose_protected_raw(&[0x81, 0x5f, 0x41, 0x00, 0xff]));
+        assert_eq!(mismatch(0, "COSE message", "map"), cose_protected_raw(&[0xd2, 0xa0]));
+        assert_eq!(mismatch(0, "COSE message", "empty array"), cose_protected_raw(&[0x80]));
+        assert_eq!(Err(DecodeError::TrailingBytes { offset: 2 }), cose_protected_raw(&[0x81, 0x40, 0x00]));
+    }
+}
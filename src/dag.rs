@@ -0,0 +1,233 @@
+use crate::MajorType::*;
+use crate::validate::minimal_additional;
+use crate::{at_break, decode_at, get_int, get_len, take, DecodeError};
+
+/// A way in which input breaks the rules of DAG-CBOR, the CBOR profile of IPLD, at the header at `offset`.
+#[derive(Debug, PartialEq)]
+pub enum DagCborViolation {
+    IndefiniteLength { offset: usize },
+    /// An integer, length or tag number that is encoded in more bytes than needed.
+    NonMinimal { offset: usize },
+    /// A map key that is not a text string.
+    KeyNotText { offset: usize },
+    /// A map key that does not sort after the key before it, shorter keys first and keys of the same
+    /// length bytewise. A key equal to the one before it is out of order too.
+    KeyOutOfOrder { offset: usize },
+    /// A tag other than 42, the tag of CIDs.
+    TagNotAllowed { offset: usize, tag: u64 },
+    /// A half or single precision float, where only doubles are allowed.
+    FloatNotDouble { offset: usize },
+    /// A NaN or infinite float.
+    NotFinite { offset: usize },
+    /// A simple value other than false, true and null, `value` being 23 for undefined.
+    SimpleNotAllowed { offset: usize, value: u8 },
+    /// The input is not well-formed CBOR. Nothing after this is checked.
+    Malformed(DecodeError),
+}
+
+/// Checks `bytes` against the restrictions DAG-CBOR places on CBOR: definite lengths only, integers,
+/// lengths and tag numbers in their shortest form, map keys that are text strings in length-first
+/// order, no tags but 42, no floats but finite doubles and no simple values but false, true and null.
+/// Returns every violation, in the order of their offsets.
+pub fn validate_dag_cbor(bytes: &[u8]) -> Result<(), Vec<DagCborViolation>> {
+    let mut violations = Vec::new();
+    let mut idx = 0;
+    while idx < bytes.len() {
+        if let Err(e) = check(bytes, &mut idx, &mut violations) {
+            violations.push(DagCborViolation::Malformed(e));
+            break;
+        }
+    }
+    if violations.is_empty() { Ok(()) } else { Err(violations) }
+}
+
+fn check(bytes: &[u8], idx: &mut usize, violations: &mut Vec<DagCborViolation>) -> Result<(), DecodeError> {
+    let offset = *idx;
+    let initial = *bytes.get(offset).ok_or(DecodeError::Truncated { offset })?;
+    let major_type = initial >> 5;
+    let additional = initial & 0b00011111;
+    if additional == 31 && (2..=5).contains(&major_type) {
+        violations.push(DagCborViolation::IndefiniteLength { offset });
+        *idx += 1;
+        let mut previous_key = None;
+        while !at_break(bytes, idx)? {
+            check_entry(bytes, idx, major_type, &mut previous_key, violations)?;
+        }
+        return Ok(());
+    }
+    if major_type != 7 && additional != minimal_additional(get_int(bytes, &mut offset.clone())?) {
+        violations.push(DagCborViolation::NonMinimal { offset });
+    }
+    match major_type {
+        0 | 1 => {
+            get_int(bytes, idx)?;
+        }
+        2 => {
+            let len = get_len(bytes, idx)?;
+            take(bytes, idx, len)?;
+        }
+        4 | 5 => {
+            let mut previous_key = None;
+            for _ in 0..get_int(bytes, idx)? {
+                check_entry(bytes, idx, major_type, &mut previous_key, violations)?;
+            }
+        }
+        6 => {
+            let tag = get_int(bytes, idx)?;
+            if tag != 42 {
+                violations.push(DagCborViolation::TagNotAllowed { offset, tag });
+            }
+            check(bytes, idx, violations)?;
+        }
+        7 => match decode_at(bytes, idx)? {
+            F16(v) | F32(v) => {
+                violations.push(DagCborViolation::FloatNotDouble { offset });
+                if !v.is_finite() {
+                    violations.push(DagCborViolation::NotFinite { offset });
+                }
+            }
+            F64(v) if !v.is_finite() => violations.push(DagCborViolation::NotFinite { offset }),
+            Undefined => violations.push(DagCborViolation::SimpleNotAllowed { offset, value: 23 }),
+            Simple(value) => violations.push(DagCborViolation::SimpleNotAllowed { offset, value }),
+            _ => {}
+        },
+        _ => {
+            // text strings are decoded to check their UTF-8
+            decode_at(bytes, idx)?;
+        }
+    }
+    Ok(())
+}
+
+/// Checks the next element of an array or string, or the next entry of a map, whose key must come
+/// after `previous_key`.
+fn check_entry<'a>(bytes: &'a [u8], idx: &mut usize, major_type: u8, previous_key: &mut Option<&'a [u8]>,
+                   violations: &mut Vec<DagCborViolation>) -> Result<(), DecodeError> {
+    if major_type != 5 {
+        return check(bytes, idx, violations);
+    }
+    let offset = *idx;
+    if bytes.get(offset).is_some_and(|initial| initial >> 5 != 3) {
+        violations.push(DagCborViolation::KeyNotText { offset });
+    }
+    check(bytes, idx, violations)?;
+    // the encoded keys compare as their content does: the header grows with the length
+    let key = &bytes[offset..*idx];
+    if previous_key.is_some_and(|previous| (key.len(), key) <= (previous.len(), previous)) {
+        violations.push(DagCborViolation::KeyOutOfOrder { offset });
+    }
+    *previous_key = Some(key);
+    check(bytes, idx, violations)
+}
+
+/// Formats the content of a tag 42 byte string as a CID string: base58btc for version 0 CIDs and
+/// multibase base32 for later versions. Returns `None` if it is not a CID.
+pub(crate) fn render_cid(bytes: &[u8]) -> Option<String> {
+    // the byte string starts with the multibase prefix of binary data
+    let [0, cid @ ..] = bytes else { return None };
+    match cid {
+        [] => None,
+        [0x12, 0x20, digest @ ..] if digest.len() == 32 => Some(base58(cid)),
+        _ => Some(format!("b{}", base32(cid))),
+    }
+}
+
+/// RFC 4648 base32 in lowercase and without padding, as multibase uses it.
+fn base32(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 32] = b"abcdefghijklmnopqrstuvwxyz234567";
+    let mut out = String::new();
+    let (mut buffer, mut bits) = (0u32, 0);
+    for &b in bytes {
+        buffer = buffer << 8 | b as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(ALPHABET[(buffer >> bits) as usize & 31] as char);
+        }
+    }
+    if bits > 0 {
+        out.push(ALPHABET[(buffer << (5 - bits)) as usize & 31] as char);
+    }
+    out
+}
+
+/// Base58 with the Bitcoin alphabet, where leading zero bytes become `1`s.
+fn base58(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+    // the digits in base 58, least significant first
+    let mut digits: Vec<u8> = Vec::new();
+    for &b in bytes {
+        let mut carry = b as u32;
+        for digit in digits.iter_mut() {
+            carry += (*digit as u32) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+    let zeros = bytes.iter().take_while(|&&b| b == 0).count();
+    std::iter::repeat_n('1', zeros).chain(digits.iter().rev().map(|&d| ALPHABET[d as usize] as char)).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::decode;
+    use crate::DecodeError;
+
+    /// The SHA-256 digest of "hello".
+    const DIGEST: [u8; 32] = [44, 242, 77, 186, 95, 176, 163, 14, 38, 232, 59, 42, 197, 185, 226, 158,
+        27, 22, 30, 92, 31, 167, 66, 94, 115, 4, 51, 98, 147, 139, 152, 36];
+
+    #[test]
+    fn valid_map() {
+        // {"a": 1, "bb": 42(h'0001550000'), "cc": 1.5 as a double}
+        let bytes = [0xa3, 0x61, b'a', 0x01, 0x62, b'b', b'b', 0xd8, 0x2a, 0x45, 0x00, 0x01, 0x55, 0x00, 0x00,
+            0x62, b'c', b'c', 0xfb, 0x3f, 0xf8, 0, 0, 0, 0, 0, 0];
+        assert_eq!(Ok(()), validate_dag_cbor(&bytes));
+        assert_eq!("[Map({Str(\"a\"): U(1), Str(\"bb\"): Cid(\"bafkqaaa\"), Str(\"cc\"): F64(1.5)})]", decode(&bytes));
+    }
+
+    #[test]
+    fn cid_versions() {
+        let mut v0 = vec![0x00, 0x12, 0x20];
+        v0.extend(DIGEST);
+        assert_eq!(Some("QmRN6wdp1S2A5EtjW9A3M1vKSBuQQGcgvuhoMUoEz4iiT5".to_string()), render_cid(&v0));
+        // version 1, dag-cbor
+        let mut v1 = vec![0x00, 0x01, 0x71, 0x12, 0x20];
+        v1.extend(DIGEST);
+        assert_eq!(Some("bafyreibm6jg3ux5qumhcn2b3flc3tyu6dmlb4xa7u5bf44yegnrjhc4yeq".to_string()), render_cid(&v1));
+        assert_eq!(None, render_cid(&[0x01, 0x55]));
+        assert_eq!(None, render_cid(&[]));
+    }
+
+    #[test]
+    fn each_violation() {
+        use DagCborViolation::*;
+        assert_eq!(Err(vec![IndefiniteLength { offset: 0 }]), validate_dag_cbor(&[0x9f, 0x01, 0xff]));
+        assert_eq!(Err(vec![KeyNotText { offset: 1 }]), validate_dag_cbor(&[0xa1, 0x01, 0x02]));
+        // {"aa": 1, "b": 2}, as shorter keys come first; {"a": 1, "a": 2}
+        assert_eq!(Err(vec![KeyOutOfOrder { offset: 5 }]), validate_dag_cbor(&[0xa2, 0x62, b'a', b'a', 0x01, 0x61, b'b', 0x02]));
+        assert_eq!(Err(vec![KeyOutOfOrder { offset: 4 }]), validate_dag_cbor(&[0xa2, 0x61, b'a', 0x01, 0x61, b'a', 0x02]));
+        assert_eq!(Err(vec![TagNotAllowed { offset: 1, tag: 1 }]), validate_dag_cbor(&[0x81, 0xc1, 0x00]));
+        assert_eq!(Err(vec![FloatNotDouble { offset: 0 }, FloatNotDouble { offset: 3 }]),
+                   validate_dag_cbor(&[0xf9, 0x3c, 0x00, 0xfa, 0x3f, 0x80, 0x00, 0x00]));
+        // [1 in two bytes, "a" with a one-byte length, 42 with a tag number in two bytes(h'00')]
+        assert_eq!(Err(vec![NonMinimal { offset: 1 }, NonMinimal { offset: 3 }, NonMinimal { offset: 6 }]),
+                   validate_dag_cbor(&[0x83, 0x18, 0x01, 0x78, 0x01, b'a', 0xd9, 0x00, 0x2a, 0x41, 0x00]));
+        // NaN and -Infinity as doubles, NaN as a half
+        let mut bytes = vec![0xfb, 0x7f, 0xf8, 0, 0, 0, 0, 0, 0, 0xfb, 0xff, 0xf0, 0, 0, 0, 0, 0, 0];
+        bytes.extend([0xf9, 0x7e, 0x00]);
+        assert_eq!(Err(vec![NotFinite { offset: 0 }, NotFinite { offset: 9 }, FloatNotDouble { offset: 18 }, NotFinite { offset: 18 }]),
+                   validate_dag_cbor(&bytes));
+        // false, true, null, undefined, simple(16), simple(255)
+        assert_eq!(Err(vec![SimpleNotAllowed { offset: 3, value: 23 }, SimpleNotAllowed { offset: 4, value: 16 },
+                            SimpleNotAllowed { offset: 5, value: 255 }]),
+                   validate_dag_cbor(&[0xf4, 0xf5, 0xf6, 0xf7, 0xf0, 0xf8, 0xff]));
+        assert_eq!(Err(vec![IndefiniteLength { offset: 0 }, Malformed(DecodeError::Truncated { offset: 2 })]),
+                   validate_dag_cbor(&[0xbf, 0x61]));
+    }
+}
@@ -0,0 +1,171 @@
+use crate::MajorType::{self, *};
+
+/// The largest exponent, either way, that decimal fractions and bigfloats are written out for.
+/// It covers every `f64`, while bounding the digits a hostile exponent could make the output grow by.
+const MAX_EXPONENT: i128 = 1100;
+
+/// Renders tags 4 (decimal fraction) and 5 (bigfloat) as their exact value in decimal, e.g.
+/// `DecimalFraction("273.15")` for `4([-2, 27315])` and `Bigfloat("1.5")` for `5([-1, 3])`.
+/// The mantissa can be an integer or a bignum of any size. Returns `None` for other tags, for
+/// content that is not laid out as expected and for exponents beyond [`MAX_EXPONENT`].
+pub(crate) fn render(number: u64, content: &MajorType) -> Option<String> {
+    let name = match number {
+        4 => "DecimalFraction",
+        5 => "Bigfloat",
+        _ => return None,
+    };
+    let Arr(parts) = content else { return None };
+    let [exponent, mantissa] = parts.as_slice() else { return None };
+    // the exponent is an integer, never a bignum
+    let exponent = match exponent {
+        U(v) => *v as i128,
+        N(v) => *v,
+        _ => return None,
+    };
+    if exponent.abs() > MAX_EXPONENT {
+        return None;
+    }
+    let mantissa = match mantissa {
+        U(v) => v.to_string(),
+        N(v) => v.to_string(),
+        Tag(sign @ (2 | 3), magnitude) => match &**magnitude {
+            BStr(magnitude) => bignum_decimal(*sign == 3, magnitude),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    let (sign, digits) = match mantissa.strip_prefix('-') {
+        Some(digits) => ("-", digits),
+        None => ("", mantissa.as_str()),
+    };
+    if digits == "0" {
+        // zero is zero whatever the exponent, rather than `000` or `0.00`
+        return Some(format!("{}(\"0\")", name));
+    }
+    let mut digits = digits.bytes().map(|d| d - b'0').collect::<Vec<_>>();
+    let mut scale = 0;
+    match (number, exponent) {
+        (4, e) if e >= 0 => digits.extend(std::iter::repeat_n(0, e as usize)),
+        (4, e) => scale = -e as usize,
+        (_, e) if e >= 0 => (0..e).for_each(|_| multiply(&mut digits, 2)),
+        // m * 2^-k is m * 5^k / 10^k
+        (_, e) => {
+            (0..-e).for_each(|_| multiply(&mut digits, 5));
+            scale = -e as usize;
+        }
+    }
+    let value = format!("{}{}", sign, with_point(&digits, scale));
+    Some(format!("{}({:?})", name, value))
+}
+
+/// Multiplies the decimal `digits`, most significant first, by `factor`.
+fn multiply(digits: &mut Vec<u8>, factor: u32) {
+    let mut carry = 0;
+    for digit in digits.iter_mut().rev() {
+        let value = *digit as u32 * factor + carry;
+        *digit = (value % 10) as u8;
+        carry = value / 10;
+    }
+    while carry > 0 {
+        digits.insert(0, (carry % 10) as u8);
+        carry /= 10;
+    }
+}
+
+/// Writes `digits` with a decimal point `scale` digits from the end, e.g. `0.05` for 5 and scale 2.
+fn with_point(digits: &[u8], scale: usize) -> String {
+    let mut out = String::new();
+    if scale >= digits.len() {
+        out.push_str("0.");
+        out.extend(std::iter::repeat_n('0', scale - digits.len()));
+    }
+    for (i, digit) in digits.iter().enumerate() {
+        if i > 0 && i == digits.len() - scale {
+            out.push('.');
+        }
+        out.push((b'0' + digit) as char);
+    }
+    out
+}
+
+/// The decimal value of a bignum with big-endian `magnitude`: the magnitude itself for tag 2, or
+/// -1 minus the magnitude for tag 3.
+pub(crate) fn bignum_decimal(negative: bool, magnitude: &[u8]) -> String {
+    let mut n = magnitude.to_vec();
+    if negative {
+        // -1 - n is written as -(n + 1)
+        match n.iter().rposition(|&b| b != 0xff) {
+            Some(i) => {
+                n[i] += 1;
+                n[i + 1..].fill(0);
+            }
+            None => {
+                n.fill(0);
+                n.insert(0, 1);
+            }
+        }
+    }
+    let mut digits = Vec::new();
+    while n.iter().any(|&b| b != 0) {
+        let mut remainder = 0_u32;
+        for b in n.iter_mut() {
+            let value = remainder << 8 | *b as u32;
+            *b = (value / 10) as u8;
+            remainder = value % 10;
+        }
+        digits.push(b'0' + remainder as u8);
+    }
+    if digits.is_empty() {
+        digits.push(b'0');
+    }
+    if negative {
+        digits.push(b'-');
+    }
+    digits.reverse();
+    String::from_utf8(digits).unwrap()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::decode;
+
+    #[test]
+    fn bignum_mantissa() {
+        // 4([-2, 2(h'01' followed by 16 zero bytes)]), which is 2^128 / 100
+        let mut bytes = vec![0xc4, 0x82, 0x21, 0xc2, 0x51, 0x01];
+        bytes.extend([0; 16]);
+        assert_eq!("[DecimalFraction(\"3402823669209384634633746074317682114.56\")]", decode(&bytes));
+        // 4([3, 3(h'ffffffffffffffffffffffffffffffffff')]), which is -(2^136) * 1000
+        let mut bytes = vec![0xc4, 0x82, 0x03, 0xc3, 0x51];
+        bytes.extend([0xff; 17]);
+        assert_eq!("[DecimalFraction(\"-87112285931760246646623899502532662132736000\")]", decode(&bytes));
+    }
+
+    #[test]
+    fn decimal_fractions_and_bigfloats() {
+        // 4([-2, 27315]), 4([-3, -6]), 5([-1, 3]), 5([2, -2])
+        let bytes = [0xc4, 0x82, 0x21, 0x19, 0x6a, 0xb3, 0xc4, 0x82, 0x22, 0x25, 0xc5, 0x82, 0x20, 0x03,
+            0xc5, 0x82, 0x02, 0x21];
+        assert_eq!("[DecimalFraction(\"273.15\"), DecimalFraction(\"-0.006\"), Bigfloat(\"1.5\"), Bigfloat(\"-8\")]",
+                   decode(&bytes));
+        // 5([1, 2(h'01' followed by 16 zero bytes)])
+        let mut bytes = vec![0xc5, 0x82, 0x01, 0xc2, 0x51, 0x01];
+        bytes.extend([0; 16]);
+        assert_eq!("[Bigfloat(\"680564733841876926926749214863536422912\")]", decode(&bytes));
+        // 4([2, 0]), 5([-2, 0]), 4([-1, 2(h'0000')])
+        let bytes = [0xc4, 0x82, 0x02, 0x00, 0xc5, 0x82, 0x21, 0x00, 0xc4, 0x82, 0x20, 0xc2, 0x42, 0x00, 0x00];
+        assert_eq!("[DecimalFraction(\"0\"), Bigfloat(\"0\"), DecimalFraction(\"0\")]", decode(&bytes));
+    }
+
+    #[test]
+    fn unexpected_content_falls_back() {
+        // 4([1]), 4([1.5, 1]), 5([-2000, 1])
+        let bytes = [0xc4, 0x81, 0x01, 0xc4, 0x82, 0xf9, 0x3e, 0x00, 0x01, 0xc5, 0x82, 0x39, 0x07, 0xcf, 0x01];
+        assert_eq!("[Tag(4, Arr([U(1)])), Tag(4, Arr([F16(1.5), U(1)])), Tag(5, Arr([N(-2000), U(1)]))]", decode(&bytes));
+        // 4([3(h'7fff...ff'), 1]), whose exponent is a bignum of i128::MIN
+        let mut bytes = vec![0xc4, 0x82, 0xc3, 0x50, 0x7f];
+        bytes.extend([0xff; 15]);
+        bytes.push(0x01);
+        assert!(decode(&bytes).starts_with("[Tag(4, Arr([Tag(3, BStr("));
+    }
+}
@@ -0,0 +1,89 @@
+use std::collections::HashMap;
+
+use crate::render::Renderer;
+use crate::{decode_items_with_options, diag, DecodeOptions, TagHandler, TagRegistry};
+
+/// A configured decoder: options, tag handlers, a key dictionary and simple value names set up once and used for any
+/// number of inputs. The functions it offers do what the free functions of the same name do.
+#[derive(Clone, Default)]
+pub struct Decoder {
+    options: DecodeOptions,
+    tags: TagRegistry,
+    dictionary: Option<HashMap<u64, String>>,
+    simple_names: HashMap<u8, String>,
+}
+
+impl Decoder {
+    pub fn new(options: DecodeOptions) -> Self {
+        Decoder { options, ..Default::default() }
+    }
+
+    /// Registers `handler` for tag `number`, replacing any handler registered earlier.
+    pub fn register_tag(&mut self, number: u64, handler: TagHandler) {
+        self.tags.register_tag(number, handler);
+    }
+
+    /// Shows integer map keys that occur in `dictionary` along with their name,
+    /// as [`decode_with_dictionary`](crate::decode_with_dictionary) does.
+    pub fn set_dictionary(&mut self, dictionary: HashMap<u64, String>) {
+        self.dictionary = Some(dictionary);
+    }
+
+    /// Shows simple value `value` with `name`, e.g. `Simple(42 /sentinel/)`, replacing any name
+    /// registered for it earlier.
+    pub fn register_simple(&mut self, value: u8, name: &str) {
+        self.simple_names.insert(value, name.to_string());
+    }
+
+    pub fn options(&self) -> &DecodeOptions {
+        &self.options
+    }
+
+    pub fn decode(&self, bytes: &[u8]) -> String {
+        self.renderer(false).render(&decode_items_with_options(bytes, &self.options))
+    }
+
+    pub fn decode_pretty(&self, bytes: &[u8]) -> String {
+        self.renderer(true).render(&decode_items_with_options(bytes, &self.options))
+    }
+
+    pub fn decode_diag(&self, bytes: &[u8]) -> String {
+        diag::decode_diag_with_options(bytes, &self.options)
+    }
+
+    fn renderer(&self, pretty: bool) -> Renderer<'_> {
+        Renderer {
+            dictionary: self.dictionary.as_ref(),
+            simple_names: Some(&self.simple_names),
+            pretty,
+            ..Renderer::new(&self.tags, &self.options)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn configured_once() {
+        let mut decoder = Decoder::new(DecodeOptions { bytes_as_text_if_utf8: true, ..Default::default() });
+        decoder.register_tag(1, |content| Some(format!("Epoch({:?})", content.as_u64()?)));
+        decoder.set_dictionary(HashMap::from([(2, "name".to_string())]));
+
+        // {2: h'4869'}
+        assert_eq!("[Map({(\"name\"=2): BStr(b\"Hi\")})]", decoder.decode(&[0xa1, 0x02, 0x42, b'H', b'i']));
+        // 1(0), 1("x")
+        assert_eq!("[Epoch(0), Tag(1, Str(\"x\"))]", decoder.decode(&[0xc1, 0x00, 0xc1, 0x61, b'x']));
+        assert_eq!("[\n  Epoch(5),\n]", decoder.decode_pretty(&[0xc1, 0x05]));
+        assert_eq!("1(0)", decoder.decode_diag(&[0xc1, 0x00]));
+    }
+
+    #[test]
+    fn simple_value_names() {
+        let mut decoder = Decoder::default();
+        decoder.register_simple(42, "sentinel");
+        // [simple(42), simple(43), simple(16)]
+        assert_eq!("[Arr([Simple(42 /sentinel/), Simple(43), Simple(16)])]", decoder.decode(&[0x83, 0xf8, 0x2a, 0xf8, 0x2b, 0xf0]));
+    }
+}
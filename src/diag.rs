@@ -0,0 +1,246 @@
+use std::fmt::{self, Display, Formatter, Write};
+
+use crate::MajorType::{self, *};
+use crate::tags::tag_name;
+use crate::{decode_items, decode_items_with_options, DecodeOptions};
+
+/// Renders all top-level items in diagnostic notation (RFC 8949 section 8), separated by commas
+/// as for a CBOR sequence.
+pub fn decode_diag(bytes: &[u8]) -> String {
+    decode_items(bytes).iter()
+        .map(|item| item.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Like [`decode_diag`], but with the settings `options` has for diagnostic notation, such as
+/// [`DecodeOptions::annotate_types`].
+pub fn decode_diag_with_options(bytes: &[u8], options: &DecodeOptions) -> String {
+    decode_items_with_options(bytes, options).iter()
+        .map(|item| match options.annotate_types {
+            true => Annotated(item).to_string(),
+            false => item.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Panics unless `bytes` render as `expected` in diagnostic notation, as [`decode_diag`] renders
+/// them. The message shows both renderings one above the other, with a caret under the first
+/// character where they differ.
+#[track_caller]
+pub fn assert_decodes_to(bytes: &[u8], expected: &str) {
+    if let Some(message) = difference(expected, &decode_diag(bytes)) {
+        panic!("{}", message);
+    }
+}
+
+/// Describes where `found` stops matching `expected`, if it does.
+fn difference(expected: &str, found: &str) -> Option<String> {
+    let position = expected.chars().zip(found.chars()).take_while(|(e, f)| e == f).count();
+    if position == expected.chars().count() && position == found.chars().count() {
+        return None;
+    }
+    Some(format!("diagnostic notation differs at character {}\nexpected: {}\n   found: {}\n          {}^",
+                 position, expected, found, " ".repeat(position)))
+}
+
+impl Display for MajorType {
+    /// Formats the item in diagnostic notation.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            U(v) => write!(f, "{}", v),
+            N(v) => write!(f, "{}", v),
+            BStr(bytes) => {
+                f.write_str("h'")?;
+                for b in bytes {
+                    write!(f, "{:02x}", b)?;
+                }
+                f.write_char('\'')
+            }
+            Str(text) => write_text(text, f),
+            Arr(items) => {
+                f.write_char('[')?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                f.write_char(']')
+            }
+            Map(entries) => {
+                f.write_char('{')?;
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}: {}", key, value)?;
+                }
+                f.write_char('}')
+            }
+            Tag(number, content) => write!(f, "{}({})", number, content),
+            False => f.write_str("false"),
+            True => f.write_str("true"),
+            Null => f.write_str("null"),
+            Undefined => f.write_str("undefined"),
+            Simple(v) => write!(f, "simple({})", v),
+            F16(v) | F32(v) => write_float(*v as f64, f),
+            F64(v) => write_float(*v, f),
+            Invalid => f.write_str("/invalid/"),
+        }
+    }
+}
+
+/// Formats an item in diagnostic notation with the type of every scalar in a comment after it,
+/// e.g. `1 /uint/`, and the registered name of every tag that has one, e.g. `1(0 /uint/) /epoch-date-time/`.
+/// Arrays, maps and the items that name themselves, such as `null`, are left as they are.
+struct Annotated<'a>(&'a MajorType);
+
+impl Display for Annotated<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Arr(items) => {
+                f.write_char('[')?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}", Annotated(item))?;
+                }
+                f.write_char(']')
+            }
+            Map(entries) => {
+                f.write_char('{')?;
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}: {}", Annotated(key), Annotated(value))?;
+                }
+                f.write_char('}')
+            }
+            Tag(number, content) => {
+                write!(f, "{}({})", number, Annotated(content))?;
+                match tag_name(*number) {
+                    Some(name) => write!(f, " /{}/", name),
+                    None => Ok(()),
+                }
+            }
+            item => {
+                write!(f, "{}", item)?;
+                let hint = match item {
+                    U(_) => "uint",
+                    N(_) => "nint",
+                    BStr(_) => "bstr",
+                    Str(_) => "tstr",
+                    False | True => "bool",
+                    F16(_) => "float16",
+                    F32(_) => "float32",
+                    F64(_) => "float64",
+                    _ => return Ok(()),
+                };
+                write!(f, " /{}/", hint)
+            }
+        }
+    }
+}
+
+/// Writes `text` quoted, with the escapes JSON uses.
+pub(crate) fn write_text(text: &str, f: &mut impl Write) -> fmt::Result {
+    f.write_char('"')?;
+    for c in text.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if c.is_control() => write!(f, "\\u{:04x}", c as u32)?,
+            c => f.write_char(c)?,
+        }
+    }
+    f.write_char('"')
+}
+
+fn write_float(v: f64, f: &mut Formatter<'_>) -> fmt::Result {
+    if v.is_nan() {
+        f.write_str("NaN")
+    } else if v.is_infinite() {
+        f.write_str(if v > 0.0 { "Infinity" } else { "-Infinity" })
+    } else {
+        write!(f, "{:?}", v)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn keyword_false() {
+        assert_eq!("false", decode_diag(&[0xf4]));
+    }
+
+    #[test]
+    fn keyword_undefined() {
+        assert_eq!("undefined", decode_diag(&[0xf7]));
+    }
+
+    #[test]
+    fn simple_255() {
+        assert_eq!("simple(255)", decode_diag(&[0xf8, 0xff]));
+    }
+
+    #[test]
+    fn simple_16() {
+        assert_eq!("simple(16)", decode_diag(&[0xf0]));
+    }
+
+    #[test]
+    fn nested() {
+        // {"a\"": [1, -2, h'ff'], 1: 0(1.5)}, null
+        let bytes = [0xa2, 0x62, b'a', b'"', 0x83, 0x01, 0x21, 0x41, 0xff, 0x01, 0xc0, 0xf9, 0x3e, 0x00, 0xf6];
+        assert_eq!("{\"a\\\"\": [1, -2, h'ff'], 1: 0(1.5)}, null", decode_diag(&bytes));
+    }
+
+    #[test]
+    fn assert_matching() {
+        assert_decodes_to(&[0x82, 0x01, 0x61, b'a'], "[1, \"a\"]");
+        assert_eq!(None, difference("", ""));
+    }
+
+    #[test]
+    fn difference_points_at_divergence() {
+        assert_eq!(Some("diagnostic notation differs at character 4\nexpected: [1, 3]\n   found: [1, 2]\n              ^".to_string()),
+                   difference("[1, 3]", "[1, 2]"));
+        // one a prefix of the other
+        assert_eq!(Some("diagnostic notation differs at character 2\nexpected: [1]\n   found: [1, 2]\n            ^".to_string()),
+                   difference("[1]", "[1, 2]"));
+    }
+
+    #[test]
+    #[should_panic(expected = "diagnostic notation differs at character 1")]
+    fn assert_mismatching() {
+        assert_decodes_to(&[0x82, 0x01, 0x02], "[2, 1]");
+    }
+
+    #[test]
+    fn annotated_types() {
+        // {"a": [1, -2, h'01', true, null], 1: 1(1.5), 99: 9999("x")}
+        let bytes = [0xa3, 0x61, b'a', 0x85, 0x01, 0x21, 0x41, 0x01, 0xf5, 0xf6, 0x01, 0xc1, 0xf9, 0x3e, 0x00,
+            0x18, 0x63, 0xd9, 0x27, 0x0f, 0x61, b'x'];
+        assert_eq!("{\"a\": [1, -2, h'01', true, null], 1: 1(1.5), 99: 9999(\"x\")}", decode_diag(&bytes));
+        let options = DecodeOptions { annotate_types: true, ..Default::default() };
+        assert_eq!("{\"a\" /tstr/: [1 /uint/, -2 /nint/, h'01' /bstr/, true /bool/, null], \
+                    1 /uint/: 1(1.5 /float16/) /epoch-date-time/, 99 /uint/: 9999(\"x\" /tstr/)}",
+                   decode_diag_with_options(&bytes, &options));
+        assert_eq!(decode_diag(&bytes), decode_diag_with_options(&bytes, &DecodeOptions::default()));
+    }
+
+    #[test]
+    fn negative_zero() {
+        assert_eq!("-0.0, -0.0, -0.0, 0.0",
+                   decode_diag(&[0xf9, 0x80, 0x00, 0xfa, 0x80, 0, 0, 0, 0xfb, 0x80, 0, 0, 0, 0, 0, 0, 0, 0xf9, 0x00, 0x00]));
+    }
+}
@@ -0,0 +1,171 @@
+use std::fmt::Write;
+use std::ops::Range;
+
+use crate::MajorType::{self, *};
+use crate::{at_break, decode_at, decode_single, get_int, get_len, take, DecodeError, Path, Segment};
+
+/// A decoded item that remembers where in the input each of its nodes came from, so that the
+/// original bytes of any node can be taken out without re-encoding it.
+pub struct Document<'a> {
+    bytes: &'a [u8],
+    root: MajorType,
+    span: Span,
+}
+
+/// The input range of a node and of its children: the items of an array, the keys and values of a
+/// map in turn, the content of a tag, or the chunks of an indefinite-length string.
+struct Span {
+    range: Range<usize>,
+    children: Vec<Span>,
+}
+
+/// Decodes `bytes` as exactly one item, like [`decode_single`], keeping track of the input range
+/// of every node.
+pub fn decode_document(bytes: &[u8]) -> Result<Document<'_>, DecodeError> {
+    let root = decode_single(bytes)?;
+    let span = span(bytes, &mut 0)?;
+    Ok(Document { bytes, root, span })
+}
+
+impl<'a> Document<'a> {
+    pub fn root(&self) -> &MajorType {
+        &self.root
+    }
+
+    /// The original bytes of the node at `path`, or `None` if there is no such node.
+    /// As in [`find`](crate::find), the content of a tag has the same path as the tag; the bytes
+    /// returned then include the tag.
+    pub fn raw_slice(&self, path: &Path) -> Option<&'a [u8]> {
+        let mut item = &self.root;
+        let mut span = &self.span;
+        for segment in &path.0 {
+            while let Tag(_, content) = item {
+                item = content;
+                span = &span.children[0];
+            }
+            (item, span) = match (item, segment) {
+                (Arr(items), Segment::Index(i)) => (items.get(*i)?, span.children.get(*i)?),
+                (Map(entries), Segment::Key(key)) => {
+                    let i = entries.iter().position(|(k, _)| k == key)?;
+                    (&entries[i].1, &span.children[2 * i + 1])
+                }
+                _ => return None,
+            };
+        }
+        Some(&self.bytes[span.range.clone()])
+    }
+}
+
+/// Decodes `bytes` as exactly one item and renders it with the encoded size of every scalar after
+/// it, e.g. `Arr([U(1) /1 byte/, U(1000) /3 bytes/])`. The size includes the header, so that the
+/// sizes of all scalars and container headers add up to the length of the input.
+pub fn decode_with_sizes(bytes: &[u8]) -> Result<String, DecodeError> {
+    let document = decode_document(bytes)?;
+    let mut out = String::new();
+    render_sizes(&document.root, &document.span, &mut out);
+    Ok(out)
+}
+
+fn render_sizes(item: &MajorType, span: &Span, out: &mut String) {
+    match item {
+        Arr(items) => {
+            out.push_str("Arr([");
+            for (i, (item, span)) in items.iter().zip(&span.children).enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                render_sizes(item, span, out);
+            }
+            out.push_str("])");
+        }
+        Map(entries) => {
+            out.push_str("Map({");
+            for (i, ((key, value), spans)) in entries.iter().zip(span.children.chunks(2)).enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                render_sizes(key, &spans[0], out);
+                out.push_str(": ");
+                render_sizes(value, &spans[1], out);
+            }
+            out.push_str("})");
+        }
+        Tag(number, content) => {
+            write!(out, "Tag({}, ", number).unwrap();
+            render_sizes(content, &span.children[0], out);
+            out.push(')');
+        }
+        // the chunks of an indefinite-length string count as one scalar
+        scalar => {
+            let size = span.range.len();
+            write!(out, "{:?} /{} byte{}/", scalar, size, if size == 1 { "" } else { "s" }).unwrap();
+        }
+    }
+}
+
+/// Finds the span of the item at `idx` and advances `idx` past it, as [`skip`](crate::skip) does.
+fn span(bytes: &[u8], idx: &mut usize) -> Result<Span, DecodeError> {
+    let start = *idx;
+    let initial = *bytes.get(start).ok_or(DecodeError::Truncated { offset: start })?;
+    let major_type = initial >> 5;
+    let mut children = Vec::new();
+    if initial & 0b00011111 == 31 && (2..=5).contains(&major_type) {
+        *idx += 1;
+        while !at_break(bytes, idx)? {
+            children.push(span(bytes, idx)?);
+        }
+    } else {
+        match major_type {
+            2 | 3 => {
+                let len = get_len(bytes, idx)?;
+                take(bytes, idx, len)?;
+            }
+            4 | 5 => {
+                let len = get_int(bytes, idx)?;
+                let items = if major_type == 5 { len.saturating_mul(2) } else { len };
+                for _ in 0..items {
+                    children.push(span(bytes, idx)?);
+                }
+            }
+            6 => {
+                get_int(bytes, idx)?;
+                children.push(span(bytes, idx)?);
+            }
+            _ => {
+                decode_at(bytes, idx)?;
+            }
+        }
+    }
+    Ok(Span { range: start..*idx, children })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn raw_map_value() {
+        // {"payload": 24({_ 1: 2 in two bytes}), "sig": [h'0102']}
+        let bytes = [0xa2, 0x67, b'p', b'a', b'y', b'l', b'o', b'a', b'd', 0xd8, 0x18, 0xbf, 0x01, 0x18, 0x02, 0xff,
+            0x63, b's', b'i', b'g', 0x81, 0x42, 0x01, 0x02];
+        let document = decode_document(&bytes).unwrap();
+        let payload = Path(vec![Segment::Key(Str("payload".into()))]);
+        assert_eq!(Some(&bytes[9..16]), document.raw_slice(&payload));
+        assert_eq!(Some(&bytes[13..15]), document.raw_slice(&payload.child(Segment::Key(U(1)))));
+        let sig = Path(vec![Segment::Key(Str("sig".into())), Segment::Index(0)]);
+        assert_eq!(Some(&bytes[21..]), document.raw_slice(&sig));
+        assert_eq!(Some(&bytes[..]), document.raw_slice(&Path::default()));
+        assert_eq!(None, document.raw_slice(&Path(vec![Segment::Index(0)])));
+    }
+
+    #[test]
+    fn sizes_of_scalars() {
+        // [1, 1000, 100000, 2^32, {"a": -1}, 1("x"), (_ h'01', h'02')]
+        let bytes = [0x87, 0x01, 0x19, 0x03, 0xe8, 0x1a, 0x00, 0x01, 0x86, 0xa0, 0x1b, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00,
+            0xa1, 0x61, b'a', 0x20, 0xc1, 0x61, b'x', 0x5f, 0x41, 0x01, 0x41, 0x02, 0xff];
+        assert_eq!("Arr([U(1) /1 byte/, U(1000) /3 bytes/, U(100000) /5 bytes/, U(4294967296) /9 bytes/, \
+                    Map({Str(\"a\") /2 bytes/: N(-1) /1 byte/}), Tag(1, Str(\"x\") /2 bytes/), BStr([1, 2]) /6 bytes/])",
+                   decode_with_sizes(&bytes).unwrap());
+        assert_eq!(Err(DecodeError::Truncated { offset: 1 }), decode_with_sizes(&[0x81]));
+    }
+}
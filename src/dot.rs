@@ -0,0 +1,91 @@
+use std::fmt::Write;
+
+use crate::MajorType::{self, *};
+use crate::decode_items;
+
+/// Renders the decoded items as a Graphviz DOT graph: one node per item, with edges from containers
+/// to their children labeled by array index or by map key (in diagnostic notation).
+pub fn decode_dot(bytes: &[u8]) -> String {
+    let mut graph = Graph { out: String::from("digraph cbor {\n"), nodes: 0 };
+    for item in decode_items(bytes) {
+        graph.add(&item);
+    }
+    graph.out.push_str("}\n");
+    graph.out
+}
+
+struct Graph {
+    out: String,
+    nodes: usize,
+}
+
+impl Graph {
+    /// Adds a node for `item` and its children, returning the node's id.
+    fn add(&mut self, item: &MajorType) -> usize {
+        let id = self.nodes;
+        self.nodes += 1;
+        let label = match item {
+            Arr(items) => format!("Arr({})", items.len()),
+            Map(entries) => format!("Map({})", entries.len()),
+            Tag(number, _) => format!("Tag({})", number),
+            scalar => format!("{:?}", scalar),
+        };
+        writeln!(self.out, "    n{} [label={}];", id, quote(&label)).unwrap();
+        match item {
+            Arr(items) => {
+                for (i, child) in items.iter().enumerate() {
+                    let child_id = self.add(child);
+                    self.edge(id, child_id, Some(&format!("[{}]", i)));
+                }
+            }
+            Map(entries) => {
+                for (key, value) in entries {
+                    let child_id = self.add(value);
+                    self.edge(id, child_id, Some(&key.to_string()));
+                }
+            }
+            Tag(_, content) => {
+                let child_id = self.add(content);
+                self.edge(id, child_id, None);
+            }
+            _ => {}
+        }
+        id
+    }
+
+    fn edge(&mut self, from: usize, to: usize, label: Option<&str>) {
+        match label {
+            Some(label) => writeln!(self.out, "    n{} -> n{} [label={}];", from, to, quote(label)).unwrap(),
+            None => writeln!(self.out, "    n{} -> n{};", from, to).unwrap(),
+        }
+    }
+}
+
+fn quote(label: &str) -> String {
+    format!("\"{}\"", label.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn nested() {
+        // {"a": [1, 32("x")]}
+        let dot = decode_dot(&[0xa1, 0x61, b'a', 0x82, 0x01, 0xd8, 0x20, 0x61, b'x']);
+        assert!(dot.starts_with("digraph cbor {\n"));
+        for line in [
+            r#"n0 [label="Map(1)"];"#,
+            r#"n1 [label="Arr(2)"];"#,
+            r#"n2 [label="U(1)"];"#,
+            r#"n3 [label="Tag(32)"];"#,
+            r#"n4 [label="Str(\"x\")"];"#,
+            r#"n0 -> n1 [label="\"a\""];"#,
+            r#"n1 -> n2 [label="[0]"];"#,
+            r#"n1 -> n3 [label="[1]"];"#,
+            r#"n3 -> n4;"#,
+        ] {
+            assert!(dot.contains(line), "missing {} in\n{}", line, dot);
+        }
+    }
+}
@@ -0,0 +1,335 @@
+use crate::MajorType::{self, *};
+use crate::options::EncodeOptions;
+
+/// Encodes `item` deterministically (RFC 8949 section 4.2.1): integers, lengths and floats use
+/// their shortest form and map entries are sorted by the encoded bytes of their keys.
+/// Returns `None` if the item contains an `Invalid` item or an `N` outside the CBOR range.
+pub fn encode(item: &MajorType) -> Option<Vec<u8>> {
+    encode_with_options(item, &EncodeOptions::default())
+}
+
+/// Like [`encode`], but departs from the deterministic encoding where `options` say so.
+pub fn encode_with_options(item: &MajorType, options: &EncodeOptions) -> Option<Vec<u8>> {
+    let mut out = Vec::new();
+    encode_into(item, options, &mut out)?;
+    Some(out)
+}
+
+fn encode_into(item: &MajorType, options: &EncodeOptions, out: &mut Vec<u8>) -> Option<()> {
+    match item {
+        U(v) => write_head(0, *v, out),
+        // -1 - v cannot overflow for any i128, but only -2^64..=-1 fits major type 1
+        N(v) => write_head(1, u64::try_from(-1 - *v).ok()?, out),
+        BStr(bytes) => {
+            write_head(2, bytes.len() as u64, out);
+            out.extend_from_slice(bytes);
+        }
+        Str(text) => {
+            write_head(3, text.len() as u64, out);
+            out.extend_from_slice(text.as_bytes());
+        }
+        Arr(items) => {
+            write_head(4, items.len() as u64, out);
+            for item in items {
+                encode_into(item, options, out)?;
+            }
+        }
+        Map(entries) => {
+            let mut encoded = entries.iter()
+                .map(|(key, value)| Some((encode_with_options(key, options)?, encode_with_options(value, options)?)))
+                .collect::<Option<Vec<_>>>()?;
+            if !options.preserve_order {
+                encoded.sort_by(|(a, _), (b, _)| a.cmp(b));
+            }
+            write_head(5, encoded.len() as u64, out);
+            for (key, value) in encoded {
+                out.extend(key);
+                out.extend(value);
+            }
+        }
+        Tag(number, content) => {
+            write_head(6, *number, out);
+            encode_into(content, options, out)?;
+        }
+        False => out.push(0xf4),
+        True => out.push(0xf5),
+        Null => out.push(0xf6),
+        Undefined => out.push(0xf7),
+        Simple(v) if *v < 24 => out.push(0xe0 | v),
+        Simple(v) => out.extend([0xf8, *v]),
+        F16(v) if options.preserve_float_width => match to_f16(*v) {
+            Some(half) => {
+                out.push(0xf9);
+                out.extend(half.to_be_bytes());
+            }
+            None if v.is_nan() => out.extend([0xf9, 0x7e, 0x00]),
+            // not a half float value, so it was not decoded from one
+            None => write_float(*v as f64, out),
+        },
+        F32(v) if options.preserve_float_width => {
+            out.push(0xfa);
+            out.extend(v.to_be_bytes());
+        }
+        F64(v) if options.preserve_float_width => {
+            out.push(0xfb);
+            out.extend(v.to_be_bytes());
+        }
+        F16(v) | F32(v) => write_float(*v as f64, out),
+        F64(v) => write_float(*v, out),
+        Invalid => return None,
+    }
+    Some(())
+}
+
+/// Writes a header with the shortest argument encoding for `value`.
+pub(crate) fn write_head(major_type: u8, value: u64, out: &mut Vec<u8>) {
+    let major_type = major_type << 5;
+    match value {
+        0..=23 => out.push(major_type | value as u8),
+        24..=0xff => out.extend([major_type | 24, value as u8]),
+        0x100..=0xffff => {
+            out.push(major_type | 25);
+            out.extend((value as u16).to_be_bytes());
+        }
+        0x10000..=0xffff_ffff => {
+            out.push(major_type | 26);
+            out.extend((value as u32).to_be_bytes());
+        }
+        _ => {
+            out.push(major_type | 27);
+            out.extend(value.to_be_bytes());
+        }
+    }
+}
+
+/// Writes `v` in the shortest float width that represents it exactly.
+fn write_float(v: f64, out: &mut Vec<u8>) {
+    if v.is_nan() {
+        out.extend([0xf9, 0x7e, 0x00]);
+    } else if (v as f32) as f64 != v {
+        out.push(0xfb);
+        out.extend(v.to_be_bytes());
+    } else if let Some(half) = to_f16(v as f32) {
+        out.push(0xf9);
+        out.extend(half.to_be_bytes());
+    } else {
+        out.push(0xfa);
+        out.extend((v as f32).to_be_bytes());
+    }
+}
+
+/// Converts `v` to half precision bits, if that is lossless.
+pub(crate) fn to_f16(v: f32) -> Option<u16> {
+    let bits = v.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32 - 127;
+    let mantissa = bits & 0x7fffff;
+    match exponent {
+        -127 if mantissa == 0 => Some(sign),
+        // infinities, and NaNs whose payload fits
+        128 if mantissa & 0x1fff == 0 => Some(sign | 0x7c00 | (mantissa >> 13) as u16),
+        -14..=15 if mantissa & 0x1fff == 0 => Some(sign | ((exponent + 15) as u16) << 10 | (mantissa >> 13) as u16),
+        -24..=-15 => {
+            // subnormal half: the value is m * 2^-24
+            let significand = 0x800000 | mantissa;
+            let shift = -(exponent + 1);
+            (significand & ((1 << shift) - 1) == 0).then_some(sign | (significand >> shift) as u16)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::{decode_at_offset, get_f16};
+
+    #[test]
+    fn shortest_forms() {
+        assert_eq!(Some(vec![0x18, 0x18]), encode(&U(24)));
+        assert_eq!(Some(vec![0x39, 0x01, 0x00]), encode(&N(-257)));
+        assert_eq!(Some(vec![0xf9, 0x3c, 0x00]), encode(&F64(1.0)));
+        assert_eq!(Some(vec![0xf9, 0x00, 0x01]), encode(&F32(2.0_f32.powi(-24))));
+        assert_eq!(Some(vec![0xfa, 0x47, 0xc3, 0x50, 0x00]), encode(&F64(100000.0)));
+        assert_eq!(Some(vec![0xfb, 0x3f, 0xf1, 0x99, 0x99, 0x99, 0x99, 0x99, 0x9a]), encode(&F64(1.1)));
+    }
+
+    #[test]
+    fn negative_zero_keeps_sign() {
+        assert_eq!(Some(vec![0xf9, 0x80, 0x00]), encode(&F64(-0.0)));
+        assert_eq!(Some(vec![0xf9, 0x00, 0x00]), encode(&F64(0.0)));
+    }
+
+    #[test]
+    fn float_width_preserved() {
+        let bytes = [0xfb, 0x3f, 0xf0, 0, 0, 0, 0, 0, 0];
+        let (item, _) = decode_at_offset(&bytes, 0).unwrap();
+        assert_eq!(F64(1.0), item);
+        let options = EncodeOptions { preserve_float_width: true, ..Default::default() };
+        assert_eq!(Some(bytes.to_vec()), encode_with_options(&item, &options));
+        assert_eq!(Some(vec![0xf9, 0x3c, 0x00]), encode(&item));
+        assert_eq!(Some(vec![0xfa, 0x3f, 0x80, 0, 0]), encode_with_options(&F32(1.0), &options));
+        assert_eq!(Some(vec![0xf9, 0x3c, 0x00]), encode_with_options(&F16(1.0), &options));
+        // a half NaN with a sign and payload
+        let (nan, _) = decode_at_offset(&[0xf9, 0xfc, 0x01], 0).unwrap();
+        assert_eq!(Some(vec![0xf9, 0xfc, 0x01]), encode_with_options(&nan, &options));
+        assert_eq!(Some(vec![0xf9, 0x7e, 0x00]), encode(&nan));
+    }
+
+    #[test]
+    fn map_keys_sorted() {
+        let map = Map(vec![(Str("b".into()), U(1)), (U(10), U(2)), (Str("a".into()), U(3))]);
+        assert_eq!(Some(vec![0xa3, 0x0a, 0x02, 0x61, b'a', 0x03, 0x61, b'b', 0x01]), encode(&map));
+    }
+
+    #[test]
+    fn map_order_preserved() {
+        // {"b": 1, 10: {"z": 0, "a": 0}}
+        let bytes = [0xa2, 0x61, b'b', 0x01, 0x0a, 0xa2, 0x61, b'z', 0x00, 0x61, b'a', 0x00];
+        let (item, _) = decode_at_offset(&bytes, 0).unwrap();
+        let options = EncodeOptions { preserve_order: true, ..Default::default() };
+        assert_eq!(Some(bytes.to_vec()), encode_with_options(&item, &options));
+        assert_eq!(Some(vec![0xa2, 0x0a, 0xa2, 0x61, b'a', 0x00, 0x61, b'z', 0x00, 0x61, b'b', 0x01]), encode(&item));
+    }
+
+    #[test]
+    fn invalid_not_encoded() {
+        assert_eq!(None, encode(&Arr(vec![Invalid])));
+    }
+
+    #[test]
+    fn negative_out_of_range_not_encoded() {
+        assert_eq!(None, encode(&N(0)));
+        assert_eq!(None, encode(&N(-18446744073709551617)));
+        assert_eq!(None, encode(&N(i128::MIN)));
+    }
+
+    fn item() -> impl Strategy<Value=MajorType> {
+        let leaf = prop_oneof![
+            any::<u64>().prop_map(U),
+            any::<u64>().prop_map(|v| N(-1 - v as i128)),
+            any::<Vec<u8>>().prop_map(BStr),
+            any::<String>().prop_map(|text| Str(text.into())),
+            Just(False),
+            Just(True),
+            Just(Null),
+            Just(Undefined),
+            prop_oneof![0_u8..20, 32_u8..=255].prop_map(Simple),
+            any::<u16>().prop_map(|bits| F16(get_f16(&bits.to_be_bytes()))),
+            any::<f32>().prop_map(F32),
+            any::<f64>().prop_map(F64),
+        ];
+        leaf.prop_recursive(4, 64, 8, |inner| prop_oneof![
+            prop::collection::vec(inner.clone(), 0..8).prop_map(Arr),
+            prop::collection::vec((inner.clone(), inner.clone()), 0..8).prop_map(Map),
+            (any::<u64>(), inner).prop_map(|(number, content)| Tag(number, Box::new(content))),
+        ])
+    }
+
+    /// Writes `item` in a well-formed but not necessarily canonical way: argument widths are
+    /// padded by `widen` extra sizes and floats keep the width of their variant.
+    fn write_loose(item: &MajorType, widen: &mut impl Iterator<Item=u8>, out: &mut Vec<u8>) {
+        let mut head = |major_type: u8, value: u64, out: &mut Vec<u8>| {
+            let minimal: u8 = match value {
+                0..=23 => 0,
+                24..=0xff => 1,
+                0x100..=0xffff => 2,
+                0x10000..=0xffff_ffff => 3,
+                _ => 4,
+            };
+            match (minimal + widen.next().unwrap_or(0)).min(4) {
+                0 => out.push(major_type << 5 | value as u8),
+                width => {
+                    out.push(major_type << 5 | (23 + width));
+                    out.extend(&value.to_be_bytes()[8 - (1 << (width - 1))..]);
+                }
+            }
+        };
+        match item {
+            U(v) => head(0, *v, out),
+            N(v) => head(1, (-1 - *v) as u64, out),
+            BStr(bytes) => {
+                head(2, bytes.len() as u64, out);
+                out.extend(bytes);
+            }
+            Str(text) => {
+                head(3, text.len() as u64, out);
+                out.extend(text.as_bytes());
+            }
+            Arr(items) => {
+                head(4, items.len() as u64, out);
+                for item in items {
+                    write_loose(item, widen, out);
+                }
+            }
+            Map(entries) => {
+                head(5, entries.len() as u64, out);
+                for (key, value) in entries {
+                    write_loose(key, widen, out);
+                    write_loose(value, widen, out);
+                }
+            }
+            Tag(number, content) => {
+                head(6, *number, out);
+                write_loose(content, widen, out);
+            }
+            F16(v) => {
+                out.push(0xf9);
+                out.extend(to_f16(*v).unwrap_or(0x7e00).to_be_bytes());
+            }
+            F32(v) => {
+                out.push(0xfa);
+                out.extend(v.to_be_bytes());
+            }
+            F64(v) => {
+                out.push(0xfb);
+                out.extend(v.to_be_bytes());
+            }
+            scalar => out.extend(encode(scalar).unwrap()),
+        }
+    }
+
+    /// Equality that ignores what canonical encoding is allowed to change:
+    /// float width, NaN payloads and the order of map entries.
+    fn same_structure(a: &MajorType, b: &MajorType) -> bool {
+        let float = |item: &MajorType| match item {
+            F16(v) | F32(v) => Some(*v as f64),
+            F64(v) => Some(*v),
+            _ => None,
+        };
+        match (a, b) {
+            (Arr(a), Arr(b)) => a.len() == b.len() && a.iter().zip(b).all(|(a, b)| same_structure(a, b)),
+            (Map(a), Map(b)) => {
+                fn sorted(entries: &[(MajorType, MajorType)]) -> Vec<&(MajorType, MajorType)> {
+                    let mut entries = entries.iter().collect::<Vec<_>>();
+                    entries.sort_by_key(|(key, _)| encode(key));
+                    entries
+                }
+                a.len() == b.len() && sorted(a).iter().zip(sorted(b))
+                    .all(|((ka, va), (kb, vb))| same_structure(ka, kb) && same_structure(va, vb))
+            }
+            (Tag(na, a), Tag(nb, b)) => na == nb && same_structure(a, b),
+            _ => match (float(a), float(b)) {
+                (Some(a), Some(b)) => a == b || (a.is_nan() && b.is_nan()),
+                _ => a == b,
+            },
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn roundtrip(item in item(), widen in prop::collection::vec(0_u8..3, 0..64)) {
+            let mut bytes = Vec::new();
+            write_loose(&item, &mut widen.into_iter(), &mut bytes);
+
+            let (decoded, len) = decode_at_offset(&bytes, 0).unwrap();
+            prop_assert_eq!(bytes.len(), len);
+            let canonical = encode(&decoded).unwrap();
+            let (redecoded, len) = decode_at_offset(&canonical, 0).unwrap();
+            prop_assert_eq!(canonical.len(), len);
+            prop_assert!(same_structure(&decoded, &redecoded), "{:?} != {:?}", decoded, redecoded);
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use std::fmt::Write;
+
+use crate::tags::tag_name;
+use crate::validate::{major_type_name, walk_all, Head};
+
+/// Explains the header of every item in `bytes`, one per line: the initial byte in binary with
+/// the three major type bits set apart from the five additional information bits, then what they
+/// mean, e.g. `0x83 = 100_00011 → major type 4 (array), length 3`.
+/// If the input is malformed, the last line says why.
+pub fn explain(bytes: &[u8]) -> String {
+    let mut lines = Vec::new();
+    let result = walk_all(bytes, &mut |head, _| lines.push(explain_head(head, bytes)));
+    if let Err(e) = result {
+        lines.push(format!("error: {}", e));
+    }
+    lines.join("\n")
+}
+
+fn explain_head(head: &Head, bytes: &[u8]) -> String {
+    let initial = bytes[head.offset];
+    let mut out = format!("{:#04x} = {:03b}_{:05b} → major type {} ({})", initial, head.major_type, head.additional,
+                          head.major_type, major_type_name(head.major_type));
+    if head.major_type == 7 {
+        out.push_str(match head.additional {
+            0..=19 => ", simple value",
+            20 => ", false",
+            21 => ", true",
+            22 => ", null",
+            23 => ", undefined",
+            24 => ", simple value in the next byte",
+            25 => ", half-precision float in the next 2 bytes",
+            26 => ", single-precision float in the next 4 bytes",
+            27 => ", double-precision float in the next 8 bytes",
+            31 => ", break",
+            _ => ", reserved",
+        });
+        if head.additional < 20 {
+            write!(out, " {}", head.additional).unwrap();
+        }
+        return out;
+    }
+    match head.additional {
+        24 => out.push_str(", argument in the next byte"),
+        25..=27 => write!(out, ", argument in the next {} bytes", 1 << (head.additional - 24)).unwrap(),
+        _ => {}
+    }
+    if head.additional == 31 {
+        out.push_str(", indefinite length");
+        return out;
+    }
+    match head.major_type {
+        0 => write!(out, ", value {}", head.argument).unwrap(),
+        1 => write!(out, ", value -1 - {} = {}", head.argument, -1 - head.argument as i128).unwrap(),
+        2..=4 => write!(out, ", length {}", head.argument).unwrap(),
+        5 => write!(out, ", {} pairs", head.argument).unwrap(),
+        _ => {
+            write!(out, ", tag {}", head.argument).unwrap();
+            if let Some(name) = tag_name(head.argument) {
+                write!(out, " ({})", name).unwrap();
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn array_and_tag() {
+        // [1, 1(100), -2]
+        let bytes = [0x83, 0x01, 0xc1, 0x18, 0x64, 0x21];
+        assert_eq!("0x83 = 100_00011 → major type 4 (array), length 3
+0x01 = 000_00001 → major type 0 (unsigned integer), value 1
+0xc1 = 110_00001 → major type 6 (tag), tag 1 (epoch-date-time)
+0x18 = 000_11000 → major type 0 (unsigned integer), argument in the next byte, value 100
+0x21 = 001_00001 → major type 1 (negative integer), value -1 - 1 = -2", explain(&bytes));
+    }
+
+    #[test]
+    fn simple_values_and_errors() {
+        // {_ "a": true}, then a truncated half float
+        let bytes = [0xbf, 0x61, b'a', 0xf5, 0xff, 0xf9, 0x3c];
+        assert_eq!("0xbf = 101_11111 → major type 5 (map), indefinite length
+0x61 = 011_00001 → major type 3 (text string), length 1
+0xf5 = 111_10101 → major type 7 (simple/float), true
+0xf9 = 111_11001 → major type 7 (simple/float), half-precision float in the next 2 bytes
+error: input ends before the item at offset 6 is complete", explain(&bytes));
+    }
+}
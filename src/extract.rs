@@ -0,0 +1,84 @@
+use crate::MajorType::{self, *};
+use crate::{decode_single, DecodeError};
+
+/// Decodes `bytes` as exactly one item and takes the value out with `extract`, failing with
+/// [`DecodeError::TypeMismatch`] if it hands the item back.
+fn decode_as<T>(bytes: &[u8], expected: &'static str, extract: impl FnOnce(MajorType) -> Result<T, MajorType>) -> Result<T, DecodeError> {
+    extract(decode_single(bytes)?).map_err(|item| DecodeError::TypeMismatch { offset: 0, expected, found: item.type_name() })
+}
+
+/// Decodes `bytes` as exactly one unsigned integer.
+pub fn decode_as_u64(bytes: &[u8]) -> Result<u64, DecodeError> {
+    decode_as(bytes, "unsigned integer", |item| item.as_u64().ok_or(item))
+}
+
+/// Decodes `bytes` as exactly one integer, unsigned or negative, that fits an `i64`.
+pub fn decode_as_i64(bytes: &[u8]) -> Result<i64, DecodeError> {
+    decode_as(bytes, "64-bit signed integer", |item| item.as_i64().ok_or(item))
+}
+
+/// Decodes `bytes` as exactly one float of any width.
+pub fn decode_as_f64(bytes: &[u8]) -> Result<f64, DecodeError> {
+    decode_as(bytes, "float", |item| match item {
+        F16(v) | F32(v) => Ok(v as f64),
+        F64(v) => Ok(v),
+        item => Err(item),
+    })
+}
+
+/// Decodes `bytes` as exactly one of `true` and `false`.
+pub fn decode_as_bool(bytes: &[u8]) -> Result<bool, DecodeError> {
+    decode_as(bytes, "boolean", |item| match item {
+        True => Ok(true),
+        False => Ok(false),
+        item => Err(item),
+    })
+}
+
+/// Decodes `bytes` as exactly one text string, definite or indefinite-length.
+pub fn decode_as_text(bytes: &[u8]) -> Result<String, DecodeError> {
+    decode_as(bytes, "text string", |item| match item {
+        Str(text) => Ok(text.to_string()),
+        item => Err(item),
+    })
+}
+
+/// Decodes `bytes` as exactly one byte string, definite or indefinite-length.
+pub fn decode_as_bytes(bytes: &[u8]) -> Result<Vec<u8>, DecodeError> {
+    decode_as(bytes, "byte string", |item| match item {
+        BStr(bytes) => Ok(bytes),
+        item => Err(item),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn matching_types() {
+        assert_eq!(Ok(1000), decode_as_u64(&[0x19, 0x03, 0xe8]));
+        assert_eq!(Ok(-1), decode_as_i64(&[0x20]));
+        assert_eq!(Ok(1.5), decode_as_f64(&[0xf9, 0x3e, 0x00]));
+        assert_eq!(Ok(true), decode_as_bool(&[0xf5]));
+        assert_eq!(Ok("ab".to_string()), decode_as_text(&[0x7f, 0x61, b'a', 0x61, b'b', 0xff]));
+        assert_eq!(Ok(vec![1, 2]), decode_as_bytes(&[0x42, 0x01, 0x02]));
+    }
+
+    #[test]
+    fn wrong_type() {
+        assert_eq!(Err(DecodeError::TypeMismatch { offset: 0, expected: "unsigned integer", found: "negative integer" }),
+                   decode_as_u64(&[0x20]));
+        assert_eq!(Err(DecodeError::TypeMismatch { offset: 0, expected: "text string", found: "byte string" }),
+                   decode_as_text(&[0x41, b'a']));
+        // 2^63 is an unsigned integer, but too large
+        assert_eq!(Err(DecodeError::TypeMismatch { offset: 0, expected: "64-bit signed integer", found: "unsigned integer" }),
+                   decode_as_i64(&[0x1b, 0x80, 0, 0, 0, 0, 0, 0, 0]));
+    }
+
+    #[test]
+    fn trailing_bytes() {
+        assert_eq!(Err(DecodeError::TrailingBytes { offset: 1 }), decode_as_u64(&[0x01, 0x02]));
+        assert_eq!(Err(DecodeError::Truncated { offset: 1 }), decode_as_bytes(&[0x41]));
+    }
+}
@@ -0,0 +1,132 @@
+use crate::MajorType::{self, *};
+use crate::{decode_single, encode_with_options, DecodeError, EncodeOptions, Path, Segment};
+
+/// The width of an encoded float.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FloatWidth {
+    Half,
+    Single,
+    Double,
+}
+
+/// Decodes `bytes` as exactly one item and encodes it with every float in `target` width, where
+/// that keeps its value. Floats that would lose precision or range keep the width they had.
+/// Map keys are left as they are, and everything else is encoded as it was decoded.
+pub fn renormalize_floats(bytes: &[u8], target: FloatWidth) -> Result<Vec<u8>, DecodeError> {
+    renormalize(bytes, target, false).map(|(encoded, _)| encoded)
+}
+
+/// Like [`renormalize_floats`], but rounds the floats that do not fit `target` to the nearest value
+/// that does, returning the paths of those that changed along with the encoding. Floats too large
+/// for `target` become infinite.
+pub fn renormalize_floats_rounding(bytes: &[u8], target: FloatWidth) -> Result<(Vec<u8>, Vec<Path>), DecodeError> {
+    renormalize(bytes, target, true)
+}
+
+fn renormalize(bytes: &[u8], target: FloatWidth, rounding: bool) -> Result<(Vec<u8>, Vec<Path>), DecodeError> {
+    let mut item = decode_single(bytes)?;
+    let mut lossy = Vec::new();
+    convert(&mut item, target, rounding, &Path::default(), &mut lossy);
+    let options = EncodeOptions { preserve_float_width: true, preserve_order: true };
+    Ok((encode_with_options(&item, &options).expect("decoded items can be encoded"), lossy))
+}
+
+fn convert(item: &mut MajorType, target: FloatWidth, rounding: bool, path: &Path, lossy: &mut Vec<Path>) {
+    let value = match item {
+        Arr(items) => {
+            for (i, item) in items.iter_mut().enumerate() {
+                convert(item, target, rounding, &path.child(Segment::Index(i)), lossy);
+            }
+            return;
+        }
+        Map(entries) => {
+            for (key, value) in entries {
+                convert(value, target, rounding, &path.child(Segment::Key(key.clone())), lossy);
+            }
+            return;
+        }
+        Tag(_, content) => return convert(content, target, rounding, path, lossy),
+        F16(v) | F32(v) => *v as f64,
+        F64(v) => *v,
+        _ => return,
+    };
+    let rounded = match target {
+        FloatWidth::Half => round_to_half(value),
+        FloatWidth::Single => value as f32 as f64,
+        FloatWidth::Double => value,
+    };
+    // NaN payloads are not kept, so any NaN counts as the same value
+    if rounded != value && !value.is_nan() {
+        if !rounding {
+            return;
+        }
+        lossy.push(path.clone());
+    }
+    *item = match target {
+        FloatWidth::Half => F16(rounded as f32),
+        FloatWidth::Single => F32(rounded as f32),
+        FloatWidth::Double => F64(rounded),
+    };
+}
+
+/// The half precision value nearest to `v`, rounding ties to even.
+fn round_to_half(v: f64) -> f64 {
+    if !v.is_finite() {
+        return v;
+    }
+    // 65520 is halfway between the largest half, 65504, and the next step up
+    if v.abs() >= 65520.0 {
+        return v.signum() * f64::INFINITY;
+    }
+    // halves have 10 fraction bits, and below 2^-14 the steps stay at those of 2^-14
+    let exponent = (((v.to_bits() >> 52) & 0x7ff) as i32 - 1023).max(-14);
+    let step = 2.0_f64.powi(exponent - 10);
+    (v / step).round_ties_even() * step
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn double(v: f64) -> Vec<u8> {
+        let mut bytes = vec![0xfb];
+        bytes.extend(v.to_be_bytes());
+        bytes
+    }
+
+    #[test]
+    fn lossless_doubles_to_halves() {
+        // [1.5, 65504.0, 2^-24, 0.1] as doubles
+        let mut bytes = vec![0x84];
+        for v in [1.5, 65504.0, 2.0_f64.powi(-24), 0.1] {
+            bytes.extend(double(v));
+        }
+        let mut expected = vec![0x84, 0xf9, 0x3e, 0x00, 0xf9, 0x7b, 0xff, 0xf9, 0x00, 0x01];
+        expected.extend(double(0.1));
+        assert_eq!(Ok(expected), renormalize_floats(&bytes, FloatWidth::Half));
+        // halves widen exactly
+        assert_eq!(Ok(vec![0xfa, 0x3f, 0xc0, 0x00, 0x00]), renormalize_floats(&[0xf9, 0x3e, 0x00], FloatWidth::Single));
+    }
+
+    #[test]
+    fn rounding_reports_loss() {
+        // {"a": 1(1.5), "b": [0.1, 100000.0]} with doubles
+        let mut bytes = vec![0xa2, 0x61, b'a', 0xc1];
+        bytes.extend(double(1.5));
+        bytes.extend([0x61, b'b', 0x82]);
+        bytes.extend(double(0.1));
+        bytes.extend(double(100000.0));
+        let (encoded, lossy) = renormalize_floats_rounding(&bytes, FloatWidth::Half).unwrap();
+        let b = Segment::Key(Str("b".into()));
+        assert_eq!(vec![Path(vec![b.clone(), Segment::Index(0)]), Path(vec![b, Segment::Index(1)])], lossy);
+        assert_eq!(vec![0xa2, 0x61, b'a', 0xc1, 0xf9, 0x3e, 0x00, 0x61, b'b', 0x82, 0xf9, 0x2e, 0x66, 0xf9, 0x7c, 0x00], encoded);
+    }
+
+    #[test]
+    fn halfway_rounds_to_even() {
+        // 1 + 2^-11 is halfway between 1 and the next half, 1 + 2^-10
+        assert_eq!(1.0, round_to_half(1.0 + 2.0_f64.powi(-11)));
+        assert_eq!(1.0 + 2.0 * 2.0_f64.powi(-10), round_to_half(1.0 + 3.0 * 2.0_f64.powi(-11)));
+        assert_eq!(0.0, round_to_half(2.0_f64.powi(-26)));
+    }
+}
@@ -0,0 +1,107 @@
+use crate::{decode_at, get_int, get_len, get_tag_number, DecodeError};
+
+/// Finds where the first item of a buffer ends, as [`item_len`](crate::item_len) does, but picking
+/// up where it left off when the buffer has grown, so that an item that arrives in many parts is
+/// only scanned once.
+#[derive(Default)]
+pub(crate) struct Framer {
+    /// Where the next header to read starts.
+    offset: usize,
+    /// For each array, map, tag and indefinite-length string the header is inside of, the number of
+    /// items it still holds, or `None` if it ends with a break.
+    open: Vec<Option<u64>>,
+}
+
+impl Framer {
+    /// Returns the length of the first item of `bytes`, failing with `Truncated` if it is not
+    /// complete yet. `bytes` must start with the bytes it was called with before.
+    pub(crate) fn frame(&mut self, bytes: &[u8]) -> Result<usize, DecodeError> {
+        loop {
+            let start = self.offset;
+            let initial = *bytes.get(start).ok_or(DecodeError::Truncated { offset: start })?;
+            let major_type = initial >> 5;
+            let mut idx = start;
+            // whether the header at `start` completes an item of whatever it is inside of
+            let complete = if initial == 0xff {
+                if self.open.last() != Some(&None) {
+                    return Err(DecodeError::UnexpectedBreak { offset: start });
+                }
+                self.open.pop();
+                idx += 1;
+                true
+            } else if initial & 0b00011111 == 31 && (2..=5).contains(&major_type) {
+                self.open.push(None);
+                idx += 1;
+                false
+            } else {
+                match major_type {
+                    2 | 3 => {
+                        let len = get_len(bytes, &mut idx)?;
+                        if bytes.len() - idx < len {
+                            return Err(DecodeError::Truncated { offset: idx });
+                        }
+                        idx += len;
+                        true
+                    }
+                    4 | 5 => {
+                        let len = get_int(bytes, &mut idx)?;
+                        let items = if major_type == 5 { len.saturating_mul(2) } else { len };
+                        if items > 0 {
+                            self.open.push(Some(items));
+                        }
+                        items == 0
+                    }
+                    6 => {
+                        get_tag_number(bytes, &mut idx)?;
+                        self.open.push(Some(1));
+                        false
+                    }
+                    _ => {
+                        decode_at(bytes, &mut idx)?;
+                        true
+                    }
+                }
+            };
+            self.offset = idx;
+            if complete && self.count_down() {
+                return Ok(self.offset);
+            }
+        }
+    }
+
+    /// Counts an item as done for what it is inside of, closing the ones that are now complete.
+    /// Returns whether that completes the first item of the buffer.
+    fn count_down(&mut self) -> bool {
+        loop {
+            match self.open.last_mut() {
+                None => return true,
+                Some(None) => return false,
+                Some(Some(remaining)) => {
+                    *remaining -= 1;
+                    if *remaining > 0 {
+                        return false;
+                    }
+                    self.open.pop();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn framing_resumes() {
+        // [_ "ab", {1: 2(h'00')}], 7, fed one byte more at a time
+        let bytes = [0x9f, 0x62, b'a', b'b', 0xa1, 0x01, 0xc2, 0x41, 0x00, 0xff, 0x07];
+        let mut framer = Framer::default();
+        for end in 1..10 {
+            assert!(matches!(framer.frame(&bytes[..end]), Err(DecodeError::Truncated { .. })), "{}", end);
+        }
+        assert_eq!(Ok(10), framer.frame(&bytes));
+        assert_eq!(Ok(3), Framer::default().frame(&[0x82, 0x01, 0x02, 0x03]));
+        assert_eq!(Err(DecodeError::UnexpectedBreak { offset: 1 }), Framer::default().frame(&[0x81, 0xff]));
+    }
+}
@@ -0,0 +1,36 @@
+use sha2::{Digest, Sha256};
+
+use crate::render::Renderer;
+use crate::{decode_all, encode, DecodeOptions, State, TagRegistry};
+
+/// Decodes `bytes` like [`decode`](crate::decode) and also returns the SHA-256 of their
+/// deterministic encoding, so that items that differ only in how they were encoded hash the same.
+/// Returns `None` if the input is malformed and so has no deterministic encoding.
+pub fn decode_and_hash(bytes: &[u8]) -> Option<(String, [u8; 32])> {
+    let items = decode_all(bytes, &mut State::default()).ok()?;
+    let mut hasher = Sha256::new();
+    for item in &items {
+        hasher.update(encode(item)?);
+    }
+    let rendered = Renderer::new(&TagRegistry::new(), &DecodeOptions::default()).render(&items);
+    Some((rendered, hasher.finalize().into()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn equal_maps_hash_the_same() {
+        // {"a": 1, "b": 2} and {_ "b": 2, "a": 1} with a two-byte 1
+        let definite = [0xa2, 0x61, b'a', 0x01, 0x61, b'b', 0x02];
+        let indefinite = [0xbf, 0x61, b'b', 0x02, 0x61, b'a', 0x18, 0x01, 0xff];
+        let (decoded, hash) = decode_and_hash(&definite).unwrap();
+        assert_eq!("[Map({Str(\"a\"): U(1), Str(\"b\"): U(2)})]", decoded);
+        assert_eq!(hash, decode_and_hash(&indefinite).unwrap().1);
+        assert_ne!(hash, decode_and_hash(&[0xa1, 0x61, b'a', 0x01]).unwrap().1);
+        assert_eq!(None, decode_and_hash(&[0x82, 0x01]));
+        // {_ 1}, which the rendering functions give the value undefined
+        assert_eq!(None, decode_and_hash(&[0xbf, 0x01, 0xff]));
+    }
+}
@@ -0,0 +1,70 @@
+use crate::{get_int, DecodeError};
+
+/// What the header of a data item says, as read by [`peek_header`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeaderInfo {
+    pub major_type: u8,
+    /// The low five bits of the initial byte.
+    pub additional_info: u8,
+    /// The length of a definite-length string, array or map, in bytes, items or entries.
+    pub declared_length: Option<u64>,
+    /// Whether this is an indefinite-length string, array or map.
+    pub indefinite: bool,
+    /// The size of the header in bytes, including the argument that follows the initial byte.
+    /// For floats and simple values that is the entire item.
+    pub header_len: usize,
+}
+
+/// Reads only the header of the first item in `bytes`, leaving its content alone, e.g. to learn
+/// that an array of 3 elements follows without decoding the elements.
+/// Fails on a break and on reserved additional information.
+pub fn peek_header(bytes: &[u8]) -> Result<HeaderInfo, DecodeError> {
+    let initial = *bytes.first().ok_or(DecodeError::Truncated { offset: 0 })?;
+    let major_type = initial >> 5;
+    let additional_info = initial & 0b00011111;
+    let is_container = (2..=5).contains(&major_type);
+    if additional_info == 31 && is_container {
+        return Ok(HeaderInfo { major_type, additional_info, declared_length: None, indefinite: true, header_len: 1 });
+    }
+    if initial == 0xff {
+        return Err(DecodeError::UnexpectedBreak { offset: 0 });
+    }
+    let mut idx = 0;
+    let argument = get_int(bytes, &mut idx)?;
+    Ok(HeaderInfo {
+        major_type,
+        additional_info,
+        declared_length: is_container.then_some(argument),
+        indefinite: false,
+        header_len: idx,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn definite_array() {
+        // an array of 1000 elements, none of them present
+        let header = peek_header(&[0x99, 0x03, 0xe8]).unwrap();
+        assert_eq!(HeaderInfo { major_type: 4, additional_info: 25, declared_length: Some(1000), indefinite: false, header_len: 3 }, header);
+    }
+
+    #[test]
+    fn indefinite_map() {
+        let header = peek_header(&[0xbf, 0x01]).unwrap();
+        assert_eq!(HeaderInfo { major_type: 5, additional_info: 31, declared_length: None, indefinite: true, header_len: 1 }, header);
+    }
+
+    #[test]
+    fn scalars() {
+        // -500 and 1.5 as a half-precision float
+        let header = peek_header(&[0x39, 0x01, 0xf3]).unwrap();
+        assert_eq!(HeaderInfo { major_type: 1, additional_info: 25, declared_length: None, indefinite: false, header_len: 3 }, header);
+        assert_eq!(3, peek_header(&[0xf9, 0x3e, 0x00]).unwrap().header_len);
+        assert_eq!(Err(DecodeError::Truncated { offset: 1 }), peek_header(&[0x1a, 0x00]));
+        assert_eq!(Err(DecodeError::Reserved { offset: 0 }), peek_header(&[0x1c]));
+        assert_eq!(Err(DecodeError::UnexpectedBreak { offset: 0 }), peek_header(&[0xff]));
+    }
+}
@@ -0,0 +1,35 @@
+/// Decodes an even number of hex digits in either case.
+pub(crate) fn decode(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) || !text.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    (0..text.len()).step_by(2)
+        .map(|i| u8::from_str_radix(&text[i..i + 2], 16).ok())
+        .collect()
+}
+
+/// Decodes hex digits in either case, ignoring ASCII whitespace between and within bytes.
+pub(crate) fn decode_spaced(text: &[u8]) -> Option<Vec<u8>> {
+    let digits = text.iter().filter(|b| !b.is_ascii_whitespace()).map(|&b| b as char).collect::<String>();
+    decode(&digits)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn digits() {
+        assert_eq!(Some(vec![0x01, 0xab, 0xff]), decode("01aBfF"));
+        assert_eq!(Some(vec![]), decode(""));
+        assert_eq!(None, decode("012"));
+        assert_eq!(None, decode("0g"));
+        assert_eq!(None, decode("+1"));
+    }
+
+    #[test]
+    fn spaced_digits() {
+        assert_eq!(Some(vec![0x83, 0x01]), decode_spaced(b" 8 3\n01\t"));
+        assert_eq!(None, decode_spaced(b"8 3 0"));
+    }
+}
@@ -0,0 +1,100 @@
+use crate::framer::Framer;
+use crate::{decode_at_offset, offset_by, DecodeError, MajorType};
+
+/// Decodes a CBOR sequence that the caller hands over in chunks, as they arrive. Unlike
+/// [`decode_stream`](crate::decode_stream) it reads nothing itself, so it works with any source.
+#[derive(Default)]
+pub struct IncrementalDecoder {
+    buffer: Vec<u8>,
+    /// Number of bytes taken out of the buffer so far, to report offsets relative to the sequence.
+    consumed: usize,
+    /// How far the item at the start of the buffer has been framed.
+    framer: Framer,
+}
+
+impl IncrementalDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `chunk` to the bytes received so far and returns the top-level items completed by it,
+    /// in order. The bytes of an incomplete item stay buffered for the next call.
+    /// An item that is not well-formed ends the list with its error. Since the items after it
+    /// cannot be told apart, the rest of the buffer is dropped, and decoding starts afresh with
+    /// the next chunk.
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<Result<MajorType, DecodeError>> {
+        self.buffer.extend_from_slice(chunk);
+        let mut items = Vec::new();
+        let mut start = 0;
+        while start < self.buffer.len() {
+            match self.framer.frame(&self.buffer[start..]) {
+                Ok(len) => {
+                    let item = decode_at_offset(&self.buffer, start).map(|(item, _)| item);
+                    items.push(item.map_err(|e| offset_by(e, self.consumed)));
+                    start += len;
+                    self.framer = Framer::default();
+                }
+                Err(DecodeError::Truncated { .. }) => break,
+                Err(e) => {
+                    items.push(Err(offset_by(e, self.consumed + start)));
+                    start = self.buffer.len();
+                    self.framer = Framer::default();
+                }
+            }
+        }
+        self.buffer.drain(..start);
+        self.consumed += start;
+        items
+    }
+
+    /// Ends the sequence, failing with [`DecodeError::Truncated`] if an item is still incomplete.
+    pub fn finish(self) -> Result<(), DecodeError> {
+        if self.buffer.is_empty() {
+            Ok(())
+        } else {
+            Err(DecodeError::Truncated { offset: self.consumed + self.buffer.len() })
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::MajorType::*;
+
+    #[test]
+    fn item_split_over_three_chunks() {
+        let mut decoder = IncrementalDecoder::new();
+        // {"a": [1, 1000]}, split in the text string and in the integer
+        assert_eq!(Vec::<Result<MajorType, DecodeError>>::new(), decoder.feed(&[0xa1, 0x61]));
+        assert!(decoder.feed(&[b'a', 0x82, 0x01, 0x19]).is_empty());
+        let items = decoder.feed(&[0x03, 0xe8, 0x01]);
+        assert_eq!(vec![Ok(Map(vec![(Str("a".into()), Arr(vec![U(1), U(1000)]))])), Ok(U(1))], items);
+        assert_eq!(Ok(()), decoder.finish());
+    }
+
+    #[test]
+    fn nested_item_one_byte_at_a_time() {
+        let mut decoder = IncrementalDecoder::new();
+        // 7, [_ "ab", {1: 2(h'00')}], then an array with a break for its item
+        let bytes = [0x07, 0x9f, 0x62, b'a', b'b', 0xa1, 0x01, 0xc2, 0x41, 0x00, 0xff, 0x81, 0xff];
+        let items = bytes.iter().map(|byte| decoder.feed(&[*byte])).collect::<Vec<_>>();
+        let nested = Arr(vec![Str("ab".into()), Map(vec![(U(1), Tag(2, Box::new(BStr(vec![0]))))])]);
+        let mut expected = bytes.iter().map(|_| Vec::new()).collect::<Vec<_>>();
+        expected[0] = vec![Ok(U(7))];
+        expected[10] = vec![Ok(nested)];
+        expected[12] = vec![Err(DecodeError::UnexpectedBreak { offset: 12 })];
+        assert_eq!(expected, items);
+    }
+
+    #[test]
+    fn errors_offset_within_sequence() {
+        let mut decoder = IncrementalDecoder::new();
+        assert_eq!(vec![Ok(U(1))], decoder.feed(&[0x01, 0x62, 0xff]));
+        // the text string is framed, but not UTF-8
+        assert_eq!(vec![Err(DecodeError::InvalidUtf8 { offset: 1, position: 0 }), Ok(U(2))], decoder.feed(&[0xff, 0x02]));
+        assert_eq!(vec![Err(DecodeError::UnexpectedBreak { offset: 6 })], decoder.feed(&[0x81, 0xff, 0x03]));
+        assert_eq!(vec![Ok(U(4))], decoder.feed(&[0x04, 0x82]));
+        assert_eq!(Err(DecodeError::Truncated { offset: 10 }), decoder.finish());
+    }
+}
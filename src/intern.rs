@@ -0,0 +1,89 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use crate::{decode_item, DecodeError, MajorType, State};
+
+/// Keeps one copy of every text map key seen while decoding, so that maps with the same keys
+/// share their storage instead of each allocating its own.
+#[derive(Debug, Default)]
+pub struct Interner {
+    keys: HashSet<Arc<str>>,
+}
+
+impl Interner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of distinct keys stored, which is also the number of key allocations made.
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    pub(crate) fn intern(&mut self, key: &str) -> Arc<str> {
+        match self.keys.get(key) {
+            Some(interned) => interned.clone(),
+            None => {
+                let interned: Arc<str> = key.into();
+                self.keys.insert(interned.clone());
+                interned
+            }
+        }
+    }
+}
+
+/// Decodes all top-level items, taking text map keys from `interner`.
+/// The interner can be reused for further calls to share keys across documents.
+pub fn decode_interned(bytes: &[u8], interner: &mut Interner) -> Result<Vec<MajorType>, DecodeError> {
+    let mut state = State { interner: Some(interner), ..Default::default() };
+    let mut items = Vec::new();
+    let mut idx = 0;
+    while idx < bytes.len() {
+        items.push(decode_item(bytes, &mut idx, &mut state)?);
+    }
+    Ok(items)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::MajorType::*;
+
+    #[test]
+    fn records_share_keys() {
+        // [{"id": i, "name": "x"}, ...] with 10,000 records
+        let mut bytes = vec![0x99, 0x27, 0x10];
+        for i in 0..10_000_u16 {
+            bytes.extend([0xa2, 0x62, b'i', b'd', 0x19]);
+            bytes.extend(i.to_be_bytes());
+            bytes.extend([0x64, b'n', b'a', b'm', b'e', 0x61, b'x']);
+        }
+        let mut interner = Interner::new();
+        let items = decode_interned(&bytes, &mut interner).unwrap();
+        assert_eq!(2, interner.len());
+
+        let Arr(records) = &items[0] else { panic!("not an array: {:?}", items[0]) };
+        assert_eq!(10_000, records.len());
+        let key = |record: &MajorType| match record {
+            Map(entries) => match &entries[1].0 {
+                Str(key) => key.clone(),
+                other => panic!("not a text key: {:?}", other),
+            },
+            other => panic!("not a map: {:?}", other),
+        };
+        assert!(Arc::ptr_eq(&key(&records[0]), &key(&records[9_999])));
+    }
+
+    #[test]
+    fn values_not_interned() {
+        let mut interner = Interner::new();
+        // {"a": "b"}, {_ "a": "c"}
+        let items = decode_interned(&[0xa1, 0x61, b'a', 0x61, b'b', 0xbf, 0x61, b'a', 0x61, b'c', 0xff], &mut interner).unwrap();
+        assert_eq!(vec![Map(vec![(Str("a".into()), Str("b".into()))]), Map(vec![(Str("a".into()), Str("c".into()))])], items);
+        assert_eq!(1, interner.len());
+    }
+}
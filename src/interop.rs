@@ -0,0 +1,88 @@
+use minicbor::{to_vec, Encode};
+
+use crate::decode;
+
+fn decoded(value: impl Encode<()>) -> String {
+    decode(&to_vec(value).unwrap())
+}
+
+#[derive(Encode)]
+enum Shape {
+    #[n(0)] Point,
+    #[n(1)] Circle(#[n(0)] u8),
+    #[n(2)] Group(#[n(0)] Vec<Shape>),
+}
+
+#[derive(Encode)]
+enum Wrapper {
+    #[n(7)] Inner(#[n(0)] Shape, #[n(1)] Option<u8>),
+}
+
+#[derive(Encode)]
+struct Pair(#[n(0)] u8, #[n(1)] String);
+
+#[derive(Encode)]
+#[cbor(map)]
+struct Settings {
+    #[n(0)] name: String,
+    #[n(1)] level: Option<u8>,
+    #[n(3)] enabled: bool,
+}
+
+#[derive(Encode)]
+#[cbor(index_only)]
+enum Level {
+    #[n(0)] Low,
+    #[n(5)] High,
+}
+
+#[test]
+fn option_none_and_some() {
+    assert_eq!("[Null]", decoded(None::<u8>));
+    assert_eq!("[U(3)]", decoded(Some(3_u8)));
+    assert_eq!("[Arr([U(1), Null, U(2)])]", decoded(vec![Some(1_u8), None, Some(2)]));
+}
+
+// variants are arrays of the index and an array of the fields
+#[test]
+fn unit_variant() {
+    assert_eq!("[Arr([U(0), Arr([])])]", decoded(Shape::Point));
+}
+
+#[test]
+fn tuple_variant() {
+    assert_eq!("[Arr([U(1), Arr([U(9)])])]", decoded(Shape::Circle(9)));
+}
+
+#[test]
+fn nested_enums() {
+    assert_eq!("[Arr([U(2), Arr([Arr([Arr([U(0), Arr([])]), Arr([U(1), Arr([U(1)])])])])])]", decoded(Shape::Group(vec![Shape::Point, Shape::Circle(1)])));
+    assert_eq!("[Arr([U(7), Arr([Arr([U(1), Arr([U(2)])]), Null])])]", decoded(Wrapper::Inner(Shape::Circle(2), None)));
+}
+
+#[test]
+fn tuple_struct() {
+    assert_eq!("[Arr([U(1), Str(\"a\")])]", decoded(Pair(1, "a".into())));
+}
+
+#[test]
+fn map_struct() {
+    assert_eq!("[Map({U(0): Str(\"x\"), U(1): U(2), U(3): True})]", decoded(Settings { name: "x".into(), level: Some(2), enabled: true }));
+}
+
+// in map mode, fields that are `None` are left out rather than written as null
+#[test]
+fn map_struct_skips_none() {
+    assert_eq!("[Map({U(0): Str(\"x\"), U(3): False})]", decoded(Settings { name: "x".into(), level: None, enabled: false }));
+}
+
+#[test]
+fn index_only_enum() {
+    assert_eq!("[Arr([U(0), U(5)])]", decoded(vec![Level::Low, Level::High]));
+}
+
+// the unit value is an empty array
+#[test]
+fn tuples_and_unit() {
+    assert_eq!("[Arr([U(1), Str(\"b\"), Arr([])])]", decoded((1_u8, "b", ())));
+}
@@ -0,0 +1,172 @@
+use crate::MajorType::{self, *};
+use crate::{at_break, check_chunk, decode_at, get_int, get_tag_number, join_chunks, DecodeError};
+
+/// Decodes the item at `offset` like [`decode_at_offset`](crate::decode_at_offset), but keeps
+/// unfinished containers on a heap-allocated stack instead of recursing, so nesting depth is
+/// limited by memory rather than by the call stack.
+///
+/// Everything else that walks a tree (dropping, comparing, rendering) still recurses,
+/// so a very deep result must be taken apart iteratively as well.
+pub fn decode_iterative(bytes: &[u8], offset: usize) -> Result<(MajorType, usize), DecodeError> {
+    let mut idx = offset;
+    let mut stack: Vec<Frame> = Vec::new();
+    loop {
+        let start = idx;
+        let initial = *bytes.get(start).ok_or(DecodeError::Truncated { offset: start })?;
+        let major_type = initial >> 5;
+
+        let mut item = if stack.last().is_some_and(Frame::is_indefinite) && at_break(bytes, &mut idx)? {
+            match stack.pop() {
+                Some(Frame::Map { key: Some(key), .. }) => return Err(DecodeError::MissingMapValue { offset: start, key }),
+                frame => frame.unwrap().finish(),
+            }
+        } else if let Some(Frame::Chunks { major_type, .. }) = stack.last() {
+            check_chunk(bytes, start, *major_type)?;
+            decode_at(bytes, &mut idx)?
+        } else if initial & 0b00011111 == 31 && (2..=5).contains(&major_type) {
+            idx += 1;
+            stack.push(Frame::new(major_type, None));
+            continue;
+        } else if major_type == 4 || major_type == 5 {
+            match get_int(bytes, &mut idx)? {
+                0 => Frame::new(major_type, Some(0)).finish(),
+                len => {
+                    stack.push(Frame::new(major_type, Some(len)));
+                    continue;
+                }
+            }
+        } else if major_type == 6 {
+            stack.push(Frame::Tag(get_tag_number(bytes, &mut idx)?));
+            continue;
+        } else {
+            decode_at(bytes, &mut idx)?
+        };
+
+        // hand the finished item to its parent, finishing parents that are now complete
+        loop {
+            match stack.last_mut() {
+                None => return Ok((item, idx)),
+                Some(Frame::Tag(number)) => {
+                    item = Tag(*number, Box::new(item));
+                    stack.pop();
+                }
+                Some(frame) => {
+                    if !frame.push(item) {
+                        break;
+                    }
+                    item = stack.pop().unwrap().finish();
+                }
+            }
+        }
+    }
+}
+
+/// A container whose contents are still being decoded. `remaining` is `None` for indefinite lengths.
+enum Frame {
+    Array { items: Vec<MajorType>, remaining: Option<u64> },
+    Map { entries: Vec<(MajorType, MajorType)>, key: Option<MajorType>, remaining: Option<u64> },
+    Chunks { major_type: u8, chunks: Vec<MajorType> },
+    Tag(u64),
+}
+
+impl Frame {
+    fn new(major_type: u8, remaining: Option<u64>) -> Self {
+        match major_type {
+            4 => Frame::Array { items: Vec::new(), remaining },
+            5 => Frame::Map { entries: Vec::new(), key: None, remaining },
+            _ => Frame::Chunks { major_type, chunks: Vec::new() },
+        }
+    }
+
+    fn is_indefinite(&self) -> bool {
+        matches!(self, Frame::Array { remaining: None, .. } | Frame::Map { remaining: None, .. } | Frame::Chunks { .. })
+    }
+
+    /// Adds a decoded item, returning whether the container is now complete.
+    fn push(&mut self, item: MajorType) -> bool {
+        let remaining = match self {
+            Frame::Array { items, remaining } => {
+                items.push(item);
+                remaining
+            }
+            Frame::Map { entries, key, remaining } => match key.take() {
+                None => {
+                    *key = Some(item);
+                    return false;
+                }
+                Some(key) => {
+                    entries.push((key, item));
+                    remaining
+                }
+            },
+            Frame::Chunks { chunks, .. } => {
+                chunks.push(item);
+                return false;
+            }
+            Frame::Tag(_) => unreachable!("tags are completed by their first item"),
+        };
+        match remaining {
+            Some(n) => {
+                *n -= 1;
+                *n == 0
+            }
+            None => false,
+        }
+    }
+
+    fn finish(self) -> MajorType {
+        match self {
+            Frame::Array { items, .. } => Arr(items),
+            Frame::Map { entries, .. } => Map(entries),
+            Frame::Chunks { major_type, chunks } => join_chunks(major_type, chunks),
+            Frame::Tag(_) => unreachable!("tags are completed by their first item"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::decode_at_offset;
+
+    /// Takes a tree apart without recursing, returning its nesting depth.
+    fn dismantle(item: MajorType) -> usize {
+        let mut max_depth = 0;
+        let mut stack = vec![(item, 0)];
+        while let Some((item, depth)) = stack.pop() {
+            max_depth = max_depth.max(depth);
+            match item {
+                Arr(items) => stack.extend(items.into_iter().map(|item| (item, depth + 1))),
+                Map(entries) => stack.extend(entries.into_iter().flat_map(|(k, v)| [(k, depth + 1), (v, depth + 1)])),
+                Tag(_, content) => stack.push((*content, depth + 1)),
+                _ => {}
+            }
+        }
+        max_depth
+    }
+
+    #[test]
+    fn deeply_nested_arrays() {
+        let mut bytes = vec![0x81; 100_000];
+        bytes.push(0x01);
+        let (item, len) = decode_iterative(&bytes, 0).unwrap();
+        assert_eq!(bytes.len(), len);
+        assert_eq!(100_000, dismantle(item));
+    }
+
+    #[test]
+    fn same_as_recursive() {
+        // [1, {_ "a": 6([_ ]), 2: (_ h'01' h'02')}, [], {}, 7(h'')], 3
+        let bytes = [0x85, 0x01, 0xbf, 0x61, b'a', 0xc6, 0x9f, 0xff, 0x02, 0x5f, 0x41, 0x01, 0x41, 0x02, 0xff, 0xff,
+            0x80, 0xa0, 0xc7, 0x40, 0x03];
+        assert_eq!(decode_at_offset(&bytes, 0), decode_iterative(&bytes, 0));
+        assert_eq!(Ok((U(3), 21)), decode_iterative(&bytes, 20));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(Err(DecodeError::Truncated { offset: 3 }), decode_iterative(&[0x82, 0x81, 0x01], 0));
+        assert_eq!(Err(DecodeError::MissingMapValue { offset: 2, key: U(1) }), decode_iterative(&[0xbf, 0x01, 0xff], 0));
+        assert_eq!(Err(DecodeError::UnexpectedBreak { offset: 1 }), decode_iterative(&[0x81, 0xff], 0));
+    }
+}
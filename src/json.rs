@@ -0,0 +1,227 @@
+use std::fmt::Write;
+
+use crate::MajorType::{self, *};
+use crate::decimal::bignum_decimal;
+use crate::diag::write_text;
+use crate::options::{JsonKeyMode, JsonNonFinite};
+use crate::{base64, decode_all, decode_items_with_options, DecodeError, DecodeOptions, Path, Segment, State};
+
+/// Renders each top-level item on one line in a JSON5-like notation meant for reading, not parsing:
+/// integer map keys stay bare (`{1: "x"}`), so they can be told apart from text keys.
+/// Otherwise the conversion follows RFC 8949 section 6.1: byte strings become base64url text,
+/// tags are left out and undefined and simple values become `null`.
+pub fn decode_json5(bytes: &[u8]) -> String {
+    decode_json5_with_options(bytes, &DecodeOptions::default())
+}
+
+/// Like [`decode_json5`], with the JSON conversion settings taken from `options`.
+pub fn decode_json5_with_options(bytes: &[u8], options: &DecodeOptions) -> String {
+    let json = Json { options, json5: true };
+    decode_items_with_options(bytes, options).iter()
+        .map(|item| {
+            let mut out = String::new();
+            json.write(item, &Path::default(), &mut out).expect("JSON5 can represent any item");
+            out
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Renders each top-level item on its own line as JSON, converted as for [`decode_json5`] except
+/// that every map key becomes text, as `options.json_key_mode` says. Fails if the input is
+/// malformed, or with [`DecodeError::NotJson`] if it has a NaN or infinite float that
+/// `options.json_nonfinite` does not allow or a key that `options.json_key_mode` does not allow.
+pub fn decode_json(bytes: &[u8], options: &DecodeOptions) -> Result<String, DecodeError> {
+    let json = Json { options, json5: false };
+    let lines = decode_all(bytes, &mut State { max_string_len: options.max_string_len, ..Default::default() })?.iter()
+        .map(|item| {
+            let mut out = String::new();
+            json.write(item, &Path::default(), &mut out)?;
+            Ok(out)
+        })
+        .collect::<Result<Vec<_>, DecodeError>>()?;
+    Ok(lines.join("\n"))
+}
+
+struct Json<'a> {
+    options: &'a DecodeOptions,
+    /// Whether integer map keys stay bare and NaN and infinities can be written as they are.
+    json5: bool,
+}
+
+impl Json<'_> {
+    fn write(&self, item: &MajorType, path: &Path, out: &mut String) -> Result<(), DecodeError> {
+        let options = self.options;
+        match item {
+            U(v) if options.integers_as_strings => write!(out, "\"{}\"", v).unwrap(),
+            N(v) if options.integers_as_strings => write!(out, "\"{}\"", v).unwrap(),
+            Tag(sign @ (2 | 3), content) if options.integers_as_strings => match &**content {
+                BStr(magnitude) => write!(out, "\"{}\"", bignum_decimal(*sign == 3, magnitude)).unwrap(),
+                content => self.write(content, path, out)?,
+            },
+            U(v) => write!(out, "{}", v).unwrap(),
+            N(v) => write!(out, "{}", v).unwrap(),
+            BStr(bytes) => write_text(&base64::encode_url(bytes), out).unwrap(),
+            Str(text) => write_text(text, out).unwrap(),
+            Arr(items) => {
+                out.push('[');
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        out.push_str(", ");
+                    }
+                    self.write(item, &path.child(Segment::Index(i)), out)?;
+                }
+                out.push(']');
+            }
+            Map(entries) => {
+                out.push('{');
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    if i > 0 {
+                        out.push_str(", ");
+                    }
+                    let path = path.child(Segment::Key(key.clone()));
+                    match key {
+                        U(v) if self.json5 => write!(out, "{}", v).unwrap(),
+                        N(v) if self.json5 => write!(out, "{}", v).unwrap(),
+                        Str(text) => write_text(text, out).unwrap(),
+                        key if self.json5 => write_text(&key.to_string(), out).unwrap(),
+                        key => self.write_key(key, &path, out)?,
+                    }
+                    out.push_str(": ");
+                    self.write(value, &path, out)?;
+                }
+                out.push('}');
+            }
+            Tag(_, content) => self.write(content, path, out)?,
+            False => out.push_str("false"),
+            True => out.push_str("true"),
+            Null | Undefined | Simple(_) | Invalid => out.push_str("null"),
+            F16(v) | F32(v) => self.write_number(*v as f64, path, out)?,
+            F64(v) => self.write_number(*v, path, out)?,
+        }
+        Ok(())
+    }
+
+    /// Writes a map key that is not text as JSON text, for the entry at `path`.
+    fn write_key(&self, key: &MajorType, path: &Path, out: &mut String) -> Result<(), DecodeError> {
+        let mut untagged = key;
+        while let Tag(_, content) = untagged {
+            untagged = content;
+        }
+        match self.options.json_key_mode {
+            JsonKeyMode::Diagnostic => write_text(&key.to_string(), out).unwrap(),
+            JsonKeyMode::Strict if matches!(untagged, Arr(_) | Map(_)) => {
+                return Err(DecodeError::NotJson { path: path.clone(), item: key.to_string() });
+            }
+            JsonKeyMode::Converted | JsonKeyMode::Strict => {
+                let mut converted = String::new();
+                self.write(key, path, &mut converted)?;
+                // byte strings and integers as strings already convert to text
+                if converted.starts_with('"') {
+                    out.push_str(&converted);
+                } else {
+                    write_text(&converted, out).unwrap();
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn write_number(&self, v: f64, path: &Path, out: &mut String) -> Result<(), DecodeError> {
+        if v.is_finite() {
+            write!(out, "{:?}", v).unwrap();
+            return Ok(());
+        }
+        let name = if v.is_nan() {
+            "NaN"
+        } else if v > 0.0 {
+            "Infinity"
+        } else {
+            "-Infinity"
+        };
+        match self.options.json_nonfinite {
+            JsonNonFinite::Error if self.json5 => out.push_str(name),
+            JsonNonFinite::Error => return Err(DecodeError::NotJson { path: path.clone(), item: name.to_string() }),
+            JsonNonFinite::Null => out.push_str("null"),
+            JsonNonFinite::String => write_text(name, out).unwrap(),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn integer_keys_bare() {
+        assert_eq!("{1: \"x\", 2: \"y\"}", decode_json5(&[0xa2, 0x01, 0x61, b'x', 0x02, 0x61, b'y']));
+    }
+
+    #[test]
+    fn integers_as_strings() {
+        // [2^53 + 1, -1, 2(h'010000000000000000000000000000000000'), 3(h'ff')], {1: 2}
+        let mut bytes = vec![0x84, 0x1b, 0x00, 0x20, 0, 0, 0, 0, 0, 0x01, 0x20, 0xc2, 0x52, 0x01];
+        bytes.extend([0; 17]);
+        bytes.extend([0xc3, 0x41, 0xff, 0xa1, 0x01, 0x02]);
+        assert_eq!("[9007199254740993, -1, \"AQAAAAAAAAAAAAAAAAAAAAAA\", \"_w\"]\n{1: 2}", decode_json5(&bytes));
+        let options = DecodeOptions { integers_as_strings: true, ..Default::default() };
+        assert_eq!("[\"9007199254740993\", \"-1\", \"87112285931760246646623899502532662132736\", \"-256\"]\n{1: \"2\"}",
+                   decode_json5_with_options(&bytes, &options));
+        // JSON keys are text already, so the integer key is not quoted again
+        let expected = "[\"9007199254740993\", \"-1\", \"87112285931760246646623899502532662132736\", \"-256\"]\n{\"1\": \"2\"}";
+        assert_eq!(Ok(expected.to_string()), decode_json(&bytes, &options));
+        assert_eq!(Ok("[9007199254740993, -1, \"AQAAAAAAAAAAAAAAAAAAAAAA\", \"_w\"]\n{\"1\": 2}".to_string()),
+                   decode_json(&bytes, &DecodeOptions::default()));
+    }
+
+    #[test]
+    fn mixed() {
+        // {"1": h'fbff', -1: [1(1.5), undefined], h'00': NaN}, true
+        let bytes = [0xa3, 0x61, b'1', 0x42, 0xfb, 0xff, 0x20, 0x82, 0xc1, 0xf9, 0x3e, 0x00, 0xf7,
+            0x41, 0x00, 0xf9, 0x7e, 0x00, 0xf5];
+        assert_eq!("{\"1\": \"-_8\", -1: [1.5, null], \"h'00'\": NaN}\ntrue", decode_json5(&bytes));
+        assert_eq!(Err(DecodeError::NotJson { path: Path(vec![Segment::Key(BStr(vec![0]))]), item: "NaN".to_string() }),
+                   decode_json(&bytes, &DecodeOptions::default()));
+    }
+
+    #[test]
+    fn nonfinite_modes() {
+        // [1.0, -Infinity, NaN]
+        let bytes = [0x83, 0xf9, 0x3c, 0x00, 0xf9, 0xfc, 0x00, 0xf9, 0x7e, 0x00];
+        let error = decode_json(&bytes, &DecodeOptions::default()).unwrap_err();
+        assert_eq!(DecodeError::NotJson { path: Path(vec![Segment::Index(1)]), item: "-Infinity".to_string() }, error);
+        assert_eq!("-Infinity at [1] cannot be written as JSON", error.to_string());
+        let options = DecodeOptions { json_nonfinite: JsonNonFinite::Null, ..Default::default() };
+        assert_eq!(Ok("[1.0, null, null]".to_string()), decode_json(&bytes, &options));
+        assert_eq!("[1.0, null, null]", decode_json5_with_options(&bytes, &options));
+        let options = DecodeOptions { json_nonfinite: JsonNonFinite::String, ..Default::default() };
+        assert_eq!(Ok("[1.0, \"-Infinity\", \"NaN\"]".to_string()), decode_json(&bytes, &options));
+        // JSON5 has literals for them
+        assert_eq!("[1.0, -Infinity, NaN]", decode_json5(&bytes));
+    }
+
+    #[test]
+    fn key_modes() {
+        // {1: 0, h'01': 0, [1, h'02']: 0}
+        let bytes = [0xa3, 0x01, 0x00, 0x41, 0x01, 0x00, 0x82, 0x01, 0x41, 0x02, 0x00];
+        assert_eq!(Ok("{\"1\": 0, \"h'01'\": 0, \"[1, h'02']\": 0}".to_string()), decode_json(&bytes, &DecodeOptions::default()));
+        let options = DecodeOptions { json_key_mode: JsonKeyMode::Converted, ..Default::default() };
+        assert_eq!(Ok("{\"1\": 0, \"AQ\": 0, \"[1, \\\"Ag\\\"]\": 0}".to_string()), decode_json(&bytes, &options));
+        let options = DecodeOptions { json_key_mode: JsonKeyMode::Strict, ..Default::default() };
+        let key = Arr(vec![U(1), BStr(vec![2])]);
+        assert_eq!(Err(DecodeError::NotJson { path: Path(vec![Segment::Key(key)]), item: "[1, h'02']".to_string() }),
+                   decode_json(&bytes, &options));
+        assert_eq!(Ok("{\"1\": 0, \"AQ\": 0}".to_string()), decode_json(&[0xa2, 0x01, 0x00, 0x41, 0x01, 0x00], &options));
+        // JSON5 keeps its own keys
+        assert_eq!("{1: 0, \"h'01'\": 0, \"[1, h'02']\": 0}", decode_json5_with_options(&bytes, &options));
+    }
+
+    #[test]
+    fn json_keys_are_text() {
+        // {1: h'01', -1: "x"}, [
+        let bytes = [0xa2, 0x01, 0x41, 0x01, 0x20, 0x61, b'x', 0x81];
+        assert_eq!(Ok("{\"1\": \"AQ\", \"-1\": \"x\"}".to_string()), decode_json(&bytes[..7], &DecodeOptions::default()));
+        assert_eq!(Err(DecodeError::Truncated { offset: 8 }), decode_json(&bytes, &DecodeOptions::default()));
+    }
+}
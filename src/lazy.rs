@@ -0,0 +1,121 @@
+use std::cell::OnceCell;
+use std::ops::Range;
+
+use crate::{at_break, decode_at, get_int, skip, DecodeError, MajorType};
+
+/// A map whose keys are decoded up front but whose values are only decoded when asked for.
+/// Until then a value is kept as the range of input bytes it occupies.
+pub struct LazyMap<'a> {
+    bytes: &'a [u8],
+    entries: Vec<Entry>,
+}
+
+struct Entry {
+    key: MajorType,
+    span: Range<usize>,
+    value: OnceCell<MajorType>,
+}
+
+/// Reads the map starting at `offset` without decoding its values, returning it together with the
+/// offset just past the map. Returns `None` if the item at `offset` is not a map.
+/// The values are still checked to be complete, so that the end of the map is known.
+pub fn decode_lazy_map(bytes: &[u8], offset: usize) -> Result<Option<(LazyMap<'_>, usize)>, DecodeError> {
+    let initial = *bytes.get(offset).ok_or(DecodeError::Truncated { offset })?;
+    if initial >> 5 != 5 {
+        return Ok(None);
+    }
+    let mut idx = offset;
+    let mut entries = Vec::new();
+    let indefinite = initial & 0b00011111 == 31;
+    let mut read_entry = |idx: &mut usize| -> Result<(), DecodeError> {
+        let key = decode_at(bytes, idx)?;
+        if indefinite && at_break(bytes, idx)? {
+            // the break came where the value should have been
+            return Err(DecodeError::MissingMapValue { offset: *idx - 1, key });
+        }
+        let start = *idx;
+        skip(bytes, idx)?;
+        entries.push(Entry { key, span: start..*idx, value: OnceCell::new() });
+        Ok(())
+    };
+    if indefinite {
+        idx += 1;
+        while !at_break(bytes, &mut idx)? {
+            read_entry(&mut idx)?;
+        }
+    } else {
+        for _ in 0..get_int(bytes, &mut idx)? {
+            read_entry(&mut idx)?;
+        }
+    }
+    Ok(Some((LazyMap { bytes, entries }, idx)))
+}
+
+impl<'a> LazyMap<'a> {
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &MajorType> {
+        self.entries.iter().map(|entry| &entry.key)
+    }
+
+    /// Returns the value of the first entry with `key`, decoding it if this is the first time it
+    /// is asked for.
+    pub fn get(&self, key: &MajorType) -> Result<Option<&MajorType>, DecodeError> {
+        let Some(entry) = self.entries.iter().find(|entry| entry.key == *key) else { return Ok(None) };
+        if let Some(value) = entry.value.get() {
+            return Ok(Some(value));
+        }
+        let value = decode_at(self.bytes, &mut entry.span.start.clone())?;
+        Ok(Some(entry.value.get_or_init(|| value)))
+    }
+
+    /// The input bytes of the value of the first entry with `key`.
+    pub fn raw(&self, key: &MajorType) -> Option<&'a [u8]> {
+        let entry = self.entries.iter().find(|entry| entry.key == *key)?;
+        Some(&self.bytes[entry.span.clone()])
+    }
+
+    /// The number of values decoded so far.
+    pub fn decoded(&self) -> usize {
+        self.entries.iter().filter(|entry| entry.value.get().is_some()).count()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::encode;
+    use crate::MajorType::*;
+
+    #[test]
+    fn only_accessed_values_decoded() {
+        let map = Map((0..1000).map(|i| (U(i), Arr(vec![Str(format!("value {}", i).into()), U(i)]))).collect());
+        let bytes = encode(&map).unwrap();
+        let (lazy, end) = decode_lazy_map(&bytes, 0).unwrap().unwrap();
+        assert_eq!(bytes.len(), end);
+        assert_eq!(1000, lazy.len());
+        assert_eq!(0, lazy.decoded());
+
+        let expected = Arr(vec![Str("value 7".into()), U(7)]);
+        assert_eq!(Ok(Some(&expected)), lazy.get(&U(7)));
+        assert_eq!(Ok(Some(&expected)), lazy.get(&U(7)));
+        assert_eq!(Ok(Some(&Arr(vec![Str("value 999".into()), U(999)]))), lazy.get(&U(999)));
+        assert_eq!(Ok(None), lazy.get(&U(1000)));
+        assert_eq!(2, lazy.decoded());
+    }
+
+    #[test]
+    fn indefinite_and_not_a_map() {
+        // {_ "a": 1}
+        let (lazy, end) = decode_lazy_map(&[0xbf, 0x61, b'a', 0x01, 0xff], 0).unwrap().unwrap();
+        assert_eq!(5, end);
+        assert_eq!(Some(&[0x01][..]), lazy.raw(&Str("a".into())));
+        assert!(decode_lazy_map(&[0x80], 0).unwrap().is_none());
+    }
+}
@@ -1,124 +1,999 @@
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
 use crate::MajorType::*;
 
+mod allocation;
+mod autoframe;
+mod base64;
+mod cose;
+mod dag;
+mod decimal;
+mod decoder;
+mod diag;
+mod document;
+mod dot;
+mod encode;
+mod explain;
+mod extract;
+mod floats;
+mod framer;
+#[cfg(feature = "hash")]
+mod hash;
+mod header;
+mod hex;
+mod incremental;
+mod intern;
+#[cfg(test)]
+mod interop;
+mod iterative;
+mod json;
+mod lazy;
+mod merge;
+mod options;
+mod query;
+mod redact;
+mod render;
+mod roundtrip;
+mod rust;
+mod sexpr;
+mod shape;
+mod sort;
+#[cfg(feature = "tokio")]
+mod stream;
+mod tags;
+mod time;
+mod tokens;
+mod validate;
+mod value;
+mod verbose;
+mod yaml;
+
+pub use allocation::decode_with_allocation_hook;
+pub use autoframe::{decode_autoframe, decode_autoframe_with_order, Framing, FramingOrder};
+pub use cose::{cose_algorithm_name, cose_protected_raw, cwt_claim_names};
+pub use dag::{validate_dag_cbor, DagCborViolation};
+pub use decoder::Decoder;
+pub use diag::{assert_decodes_to, decode_diag, decode_diag_with_options};
+pub use document::{decode_document, decode_with_sizes, Document};
+pub use dot::decode_dot;
+pub use encode::{encode, encode_with_options};
+pub use explain::explain;
+pub use extract::{decode_as_bool, decode_as_bytes, decode_as_f64, decode_as_i64, decode_as_text, decode_as_u64};
+pub use floats::{renormalize_floats, renormalize_floats_rounding, FloatWidth};
+#[cfg(feature = "hash")]
+pub use hash::decode_and_hash;
+pub use header::{peek_header, HeaderInfo};
+pub use incremental::IncrementalDecoder;
+pub use intern::{decode_interned, Interner};
+pub use iterative::decode_iterative;
+pub use json::{decode_json, decode_json5, decode_json5_with_options};
+pub use lazy::{decode_lazy_map, LazyMap};
+pub use merge::merge;
+pub use options::{BytesFormat, DecodeOptions, EncodeOptions, Indent, JsonKeyMode, JsonNonFinite, Radix};
+pub use query::{collect_bytes, decode_flat, decode_with_byte_paths, find, flatten_sorted, Path, Segment};
+pub use redact::redact;
+#[cfg(feature = "tokio")]
+pub use stream::{decode_stream, ItemStream};
+use render::Renderer;
+pub use roundtrip::{roundtrip_report, Difference, DifferenceKind, RoundtripReport};
+pub use rust::decode_to_rust;
+pub use sexpr::decode_to_sexpr;
+pub use shape::{decode_with_shape, matches_shape, Key, Shape, ShapeError};
+pub use sort::sort_map_keys;
+pub use tags::{homogeneous_array, tag_name, BytesRenderer, TagConstraint, TagHandler, TagRegistry};
+pub use tokens::{decode_tokens, Token};
+pub use validate::{validate_canonical, validate_tag_content, NonMinimal, TagMismatch};
+pub use value::Value;
+pub use verbose::{decode_verbose, Warning, WarningKind};
+pub use yaml::decode_to_yaml;
 
 pub fn decode(bytes: &[u8]) -> String {
+    decode_with_tags(bytes, &TagRegistry::new())
+}
+
+/// Like [`decode`], but renders tags that have a handler in `tags` with that handler.
+pub fn decode_with_tags(bytes: &[u8], tags: &TagRegistry) -> String {
+    Renderer::new(tags, &DecodeOptions::default()).render(&decode_items(bytes))
+}
+
+/// Like [`decode`], but shows integer map keys that occur in `dictionary` along with their name,
+/// e.g. `("temperature"=2): U(21)`.
+pub fn decode_with_dictionary(bytes: &[u8], dictionary: &HashMap<u64, String>) -> String {
+    let tags = TagRegistry::new();
+    let options = DecodeOptions::default();
+    let renderer = Renderer { dictionary: Some(dictionary), ..Renderer::new(&tags, &options) };
+    renderer.render(&decode_items(bytes))
+}
+
+/// Like [`decode`], but shows every map key as the text string `normalize_key` makes of it, e.g. to
+/// give the integer keys of one version of a message the names the next version uses.
+pub fn decode_with_key_normalizer(bytes: &[u8], normalize_key: impl Fn(&MajorType) -> String) -> String {
+    let tags = TagRegistry::new();
+    let options = DecodeOptions::default();
+    let renderer = Renderer { normalize_key: Some(&normalize_key), ..Renderer::new(&tags, &options) };
+    renderer.render(&decode_items(bytes))
+}
+
+/// Like [`decode`], but rendered as `options` say.
+pub fn decode_with_options(bytes: &[u8], options: &DecodeOptions) -> String {
+    Renderer::new(&TagRegistry::new(), options).render(&decode_items_with_options(bytes, options))
+}
+
+/// Like [`decode_with_options`], but puts every container entry on its own line, indented as `options` say.
+pub fn decode_pretty(bytes: &[u8], options: &DecodeOptions) -> String {
+    let tags = TagRegistry::new();
+    let renderer = Renderer { pretty: true, ..Renderer::new(&tags, options) };
+    renderer.render(&decode_items_with_options(bytes, options))
+}
+
+/// Decodes the top-level items, ending with `Invalid` if the input is malformed.
+fn decode_items(bytes: &[u8]) -> Vec<MajorType> {
+    decode_lenient(bytes, State { lenient: true, ..Default::default() })
+}
+
+/// Like [`decode_items`], with the limits `options` set, such as [`DecodeOptions::max_string_len`].
+fn decode_items_with_options(bytes: &[u8], options: &DecodeOptions) -> Vec<MajorType> {
+    decode_lenient(bytes, State { lenient: true, max_string_len: options.max_string_len, ..Default::default() })
+}
+
+fn decode_lenient(bytes: &[u8], mut state: State) -> Vec<MajorType> {
     let mut output = Vec::new();
     let mut idx = 0;
     while idx < bytes.len() {
-        output.push(decode_at(bytes, &mut idx));
-        idx += 1;
+        match decode_item(bytes, &mut idx, &mut state) {
+            Ok(item) => output.push(item),
+            Err(_) => {
+                output.push(Invalid);
+                break;
+            }
+        }
+    }
+    output
+}
+
+/// Decodes the item starting at `offset` and returns it together with the offset just past it.
+/// Offsets in errors are relative to the start of `bytes`, not to `offset`.
+pub fn decode_at_offset(bytes: &[u8], offset: usize) -> Result<(MajorType, usize), DecodeError> {
+    let mut idx = offset;
+    let item = decode_at(bytes, &mut idx)?;
+    Ok((item, idx))
+}
+
+/// Decodes all top-level items, failing with [`DecodeError::DepthExceeded`] for an item that is
+/// inside more than `max_depth` arrays, maps, tags and indefinite-length strings.
+/// This bounds the recursion a hostile input can cause.
+pub fn decode_with_max_depth(bytes: &[u8], max_depth: usize) -> Result<Vec<MajorType>, DecodeError> {
+    decode_all(bytes, &mut State { max_depth: Some(max_depth), ..Default::default() })
+}
+
+/// Decodes all top-level items, returning them with the number of arrays, maps, tags and
+/// indefinite-length strings the most deeply nested item is inside of. That is the smallest
+/// limit [`decode_with_max_depth`] decodes `bytes` with, to help pick one for a protocol.
+pub fn decode_with_depth(bytes: &[u8]) -> Result<(Vec<MajorType>, usize), DecodeError> {
+    let mut state = State::default();
+    let items = decode_all(bytes, &mut state)?;
+    Ok((items, state.deepest))
+}
+
+/// Decodes all top-level items, failing with [`DecodeError::BudgetExceeded`] at the header of the
+/// item that would be the `max_steps + 1`th decoded, counting every item, string chunk and tag.
+/// This bounds the work done on hostile input, unlike a timeout the same way on every run.
+pub fn decode_with_step_budget(bytes: &[u8], max_steps: usize) -> Result<Vec<MajorType>, DecodeError> {
+    decode_all(bytes, &mut State { max_steps: Some(max_steps), ..Default::default() })
+}
+
+/// Decodes all top-level items, failing with [`DecodeError::TagContentInvalid`] for the first tag
+/// whose content does not meet the constraint registered for it in `tags`.
+pub fn decode_with_tag_constraints(bytes: &[u8], tags: &TagRegistry) -> Result<Vec<MajorType>, DecodeError> {
+    decode_all(bytes, &mut State { tags: Some(tags), ..Default::default() })
+}
+
+/// Decodes `input` as [`decode`] does, but first guesses whether it is CBOR or CBOR written as hex
+/// digits, as someone might paste it. Fails where [`decode`] would end with `Invalid`.
+///
+/// Input is taken as hex if it is nothing but hex digits and ASCII whitespace, with an even number
+/// of digits. Binary CBOR rarely passes for that, as every byte would have to be one of those
+/// characters, but short input can be ambiguous: `b"00"` is hex for `0` and also the CBOR of
+/// `-17, -17`. Such input is read as hex.
+pub fn decode_auto(input: &[u8]) -> Result<String, DecodeError> {
+    let bytes = match hex::decode_spaced(input) {
+        Some(bytes) if !input.is_empty() => Cow::Owned(bytes),
+        _ => Cow::Borrowed(input),
+    };
+    let items = decode_all(&bytes, &mut State::default())?;
+    Ok(Renderer::new(&TagRegistry::new(), &DecodeOptions::default()).render(&items))
+}
+
+/// Settings and bookkeeping shared by everything decoded in one run.
+#[derive(Default)]
+struct State<'a> {
+    interner: Option<&'a mut Interner>,
+    /// Asked for permission, with the size in bytes, before anything is allocated for the output.
+    allocation_hook: Option<&'a mut dyn FnMut(usize) -> bool>,
+    /// Whether some malformed input is decoded as best it can be instead of failing, as the
+    /// rendering functions want.
+    lenient: bool,
+    /// Where to report what was decoded despite being questionable, if anywhere.
+    /// Invalid UTF-8 is decoded lossily when this is set.
+    warnings: Option<&'a mut Vec<Warning>>,
+    /// How many arrays, maps, tags and indefinite-length strings an item may be inside of.
+    max_depth: Option<usize>,
+    /// How many of them the item being decoded is inside of.
+    depth: usize,
+    /// The largest depth an item has been decoded at.
+    deepest: usize,
+    /// How many headers may be decoded.
+    max_steps: Option<usize>,
+    /// How many headers have been decoded.
+    steps: usize,
+    /// The registry whose constraints tag content must meet, if any.
+    tags: Option<&'a TagRegistry>,
+    /// The longest text or byte string allowed, in bytes.
+    max_string_len: Option<usize>,
+}
+
+impl State<'_> {
+    /// Fails if the string of `len` bytes starting at `offset` is longer than allowed.
+    fn check_string_len(&self, len: usize, offset: usize) -> Result<(), DecodeError> {
+        match self.max_string_len {
+            Some(limit) if len > limit => Err(DecodeError::StringTooLong { offset, len, limit }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Asks the allocation hook for `size` bytes on behalf of the item starting at `offset`.
+    fn allocate(&mut self, size: usize, offset: usize) -> Result<(), DecodeError> {
+        match &mut self.allocation_hook {
+            Some(hook) => match hook(size) {
+                true => Ok(()),
+                false => Err(DecodeError::AllocationDenied { offset, size }),
+            },
+            None => Ok(()),
+        }
+    }
+
+    /// Makes room in `items` for one more, asking the allocation hook first if `items` must grow.
+    fn grow<T>(&mut self, items: &mut Vec<T>, offset: usize) -> Result<(), DecodeError> {
+        if self.allocation_hook.is_some() && items.len() == items.capacity() {
+            let capacity = (items.capacity() * 2).max(4);
+            self.allocate(capacity * std::mem::size_of::<T>(), offset)?;
+            items.reserve_exact(capacity - items.len());
+        }
+        Ok(())
+    }
+}
+
+fn decode_at(bytes: &[u8], idx: &mut usize) -> Result<MajorType, DecodeError> {
+    decode_item(bytes, idx, &mut State::default())
+}
+
+/// Decodes all top-level items, stopping at the first error.
+fn decode_all(bytes: &[u8], state: &mut State) -> Result<Vec<MajorType>, DecodeError> {
+    let mut items = Vec::new();
+    let mut idx = 0;
+    while idx < bytes.len() {
+        items.push(decode_item(bytes, &mut idx, state)?);
+    }
+    Ok(items)
+}
+
+fn decode_item(bytes: &[u8], idx: &mut usize, state: &mut State) -> Result<MajorType, DecodeError> {
+    if state.max_depth.is_some_and(|max| state.depth > max) {
+        return Err(DecodeError::DepthExceeded { offset: *idx });
+    }
+    if state.max_steps.is_some_and(|max| state.steps >= max) {
+        return Err(DecodeError::BudgetExceeded { offset: *idx });
     }
-    format!("{:?}", output)
+    state.steps += 1;
+    state.deepest = state.deepest.max(state.depth);
+    state.depth += 1;
+    let item = decode_nested(bytes, idx, state);
+    state.depth -= 1;
+    item
 }
 
-fn decode_at(bytes: &[u8], mut idx: &mut usize) -> MajorType {
-    let major_type = (bytes[*idx] & 0b11100000) >> 5;
+/// Decodes the item at `idx`, which is inside `state.depth - 1` containers and tags.
+fn decode_nested(bytes: &[u8], idx: &mut usize, state: &mut State) -> Result<MajorType, DecodeError> {
+    let start = *idx;
+    let initial = *bytes.get(start).ok_or(DecodeError::Truncated { offset: start })?;
+    let major_type = (initial & 0b11100000) >> 5;
+    let indefinite = initial & 0b00011111 == 31;
 
     match major_type {
-        0 => get_int(&bytes, idx).map(|v| U(v)).unwrap_or(Invalid),
-        1 => get_int(&bytes, idx).map(|v| N(-1 - (v as i128))).unwrap_or(Invalid),
+        2 | 3 if indefinite => {
+            *idx += 1;
+            let mut chunks = Vec::new();
+            let mut well_formed = true;
+            while !at_break(bytes, idx)? {
+                match check_chunk(bytes, *idx, major_type) {
+                    Err(_) if state.lenient => well_formed = false,
+                    result => result?,
+                }
+                let chunk = decode_item(bytes, idx, state)?;
+                state.grow(&mut chunks, start)?;
+                chunks.push(chunk);
+            }
+            if !well_formed {
+                return Ok(Invalid);
+            }
+            let len = chunks.iter().map(|chunk| match chunk {
+                BStr(bytes) => bytes.len(),
+                Str(text) => text.len(),
+                _ => 0,
+            }).sum();
+            state.check_string_len(len, start)?;
+            state.allocate(len, start)?;
+            Ok(join_chunks(major_type, chunks))
+        }
+        4 if indefinite => {
+            *idx += 1;
+            Ok(Arr(items_until_break(bytes, idx, start, state)?))
+        }
+        5 if indefinite => {
+            *idx += 1;
+            let mut entries: Vec<(MajorType, MajorType)> = Vec::new();
+            while !at_break(bytes, idx)? {
+                let key = decode_key(bytes, idx, state)?;
+                if at_break(bytes, idx)? {
+                    // the break came where the value should have been
+                    if !state.lenient {
+                        return Err(DecodeError::MissingMapValue { offset: *idx - 1, key });
+                    }
+                    state.grow(&mut entries, start)?;
+                    entries.push((key, Undefined));
+                    break;
+                }
+                let value = decode_item(bytes, idx, state)?;
+                state.grow(&mut entries, start)?;
+                entries.push((key, value));
+            }
+            Ok(Map(entries))
+        }
+        0 => get_int(bytes, idx).map(U),
+        1 => get_int(bytes, idx).map(|v| N(-1 - (v as i128))),
         2 => {
-            let len = get_int(&bytes, &mut idx).unwrap() as usize;
-            let byte_string = bytes[*idx..*idx + len].to_vec();
-            *idx += len;
-            BStr(byte_string)
+            let len = get_len(bytes, idx)?;
+            state.check_string_len(len, start)?;
+            let content = take(bytes, idx, len)?;
+            state.allocate(len, start)?;
+            Ok(BStr(content.to_vec()))
         }
         3 => {
-            let len = get_int(&bytes, idx).unwrap() as usize;
-            let utf = bytes[*idx..*idx + len].to_vec();
-            *idx += len;
-            Str(String::from_utf8(utf).unwrap())
+            // the length is checked before read_text takes the content
+            state.check_string_len(get_len(bytes, &mut start.clone())?, start)?;
+            let text = match &mut state.warnings {
+                Some(warnings) => read_text_lossy(bytes, idx, warnings)?,
+                None => read_text(bytes, idx)?.into(),
+            };
+            state.allocate(text.len(), start)?;
+            Ok(Str(text.into()))
         }
         4 => {
-            let len = get_int(&bytes, idx).unwrap() as usize;
-            let mut array: Vec<MajorType> = Vec::new();
-            for _ in 0..len {
-                array.push(decode_at(bytes, idx));
-            }
-            Arr(array)
-        }
-        5 => Map(HashMap::new()),
-        6 => Tag,
-        7 => {
-            let additional = bytes[*idx] & 0b00011111;
-            let out = match additional {
-                20 => False,
-                21 => True,
-                22 => Null,
-                23 => Undefined,
-                25 => F16(get_f16(&bytes, idx)),
-                26 => F32(get_f32(&bytes, idx)),
-                27 => F64(get_f64(&bytes, idx)),
-                _ => Invalid
+            let len = get_len(bytes, idx)?;
+            // the declared length is untrusted, so arrays and maps grow as their items arrive,
+            // unless there is no hook to ask: every item takes at least one of the bytes left
+            let mut array: Vec<MajorType> = match state.allocation_hook {
+                Some(_) => Vec::new(),
+                None => Vec::with_capacity(len.min(bytes.len() - *idx)),
             };
-            *idx += 1;
-            out
+            let unchecked = state.max_depth.is_none() && state.max_steps.is_none();
+            for _ in 0..len {
+                let item = match bytes.get(*idx) {
+                    // integers below 24 are just their initial byte, and need none of the checks
+                    Some(&initial) if unchecked && initial < 0x38 && initial & 0b00011111 < 24 => {
+                        *idx += 1;
+                        state.steps += 1;
+                        state.deepest = state.deepest.max(state.depth);
+                        if initial < 0x20 { U(initial as u64) } else { N(-1 - (initial & 0b00011111) as i128) }
+                    }
+                    _ => decode_item(bytes, idx, state)?,
+                };
+                state.grow(&mut array, start)?;
+                array.push(item);
+            }
+            Ok(Arr(array))
+        }
+        5 => {
+            let len = get_len(bytes, idx)?;
+            let mut entries: Vec<(MajorType, MajorType)> = Vec::new();
+            for _ in 0..len {
+                let key = decode_key(bytes, idx, state)?;
+                let value = decode_item(bytes, idx, state)?;
+                state.grow(&mut entries, start)?;
+                entries.push((key, value));
+            }
+            Ok(Map(entries))
         }
+        6 => {
+            let number = get_tag_number(bytes, idx)?;
+            let content = decode_item(bytes, idx, state)?;
+            if let Some(tags) = state.tags {
+                tags.check(number, &content).map_err(|reason| DecodeError::TagContentInvalid { offset: start, tag: number, reason })?;
+            }
+            Ok(Tag(number, Box::new(content)))
+        }
+        _ if initial == 0xff => Err(DecodeError::UnexpectedBreak { offset: start }),
         _ => {
-            Invalid
+            let additional = initial & 0b00011111;
+            *idx += 1;
+            match additional {
+                0..=19 => Ok(Simple(additional)),
+                20 => Ok(False),
+                21 => Ok(True),
+                22 => Ok(Null),
+                23 => Ok(Undefined),
+                24 => match take(bytes, idx, 1)?[0] {
+                    // one-byte simple values below 32 must use the short form
+                    0..=31 => {
+                        *idx = start;
+                        Err(DecodeError::Reserved { offset: start })
+                    }
+                    value => Ok(Simple(value)),
+                },
+                25 => Ok(F16(get_f16(take(bytes, idx, 2)?))),
+                26 => Ok(F32(f32::from_be_bytes(to_b4(take(bytes, idx, 4)?)))),
+                27 => Ok(F64(f64::from_be_bytes(to_b8(take(bytes, idx, 8)?)))),
+                // 28 to 30 are reserved; 31 is the break, which the enclosing item consumes
+                _ => {
+                    *idx = start;
+                    Err(DecodeError::Reserved { offset: start })
+                }
+            }
+        }
+    }
+}
+
+/// Decodes a map key, taking text keys from the interner when there is one.
+fn decode_key(bytes: &[u8], idx: &mut usize, state: &mut State) -> Result<MajorType, DecodeError> {
+    match (&mut state.interner, bytes.get(*idx)) {
+        (Some(interner), Some(&initial)) if initial >> 5 == 3 && initial & 0b00011111 != 31 => {
+            read_text(bytes, idx).map(|text| Str(interner.intern(text)))
         }
+        _ => decode_item(bytes, idx, state),
     }
 }
 
-fn get_f16(bytes: &[u8], idx: &mut usize) -> f32 {
-    let b1 = bytes[*idx + 1];
-    let b2 = bytes[*idx + 2];
-    *idx += 2;
-    let sign = if (b1 & 0b10000000) == 0 { 1.0_f32 } else { -1.0_f32 };
-    let exponent = ((b1 & 0b01111100) >> 2) as i32 - 15;
-    let fraction = ((((b1 & 0b00000011) as u16) << 8) + b2 as u16) as f32;
-    2.0_f32.powi(exponent) * (1.0_f32 + fraction / 1024_f32) * sign
+/// Reads the definite-length text string at `idx`.
+fn read_text<'a>(bytes: &'a [u8], idx: &mut usize) -> Result<&'a str, DecodeError> {
+    let start = *idx;
+    let len = get_len(bytes, idx)?;
+    std::str::from_utf8(take(bytes, idx, len)?)
+        .map_err(|e| DecodeError::InvalidUtf8 { offset: start, position: e.valid_up_to() })
 }
 
-fn get_f32(bytes: &[u8], idx: &mut usize) -> f32 {
-    *idx += 4;
-    f32::from_be_bytes(to_b4(&bytes[*idx - 3..=*idx]))
+/// Like [`read_text`], but replaces invalid UTF-8 with U+FFFD and adds a warning instead of failing.
+fn read_text_lossy<'a>(bytes: &'a [u8], idx: &mut usize, warnings: &mut Vec<Warning>) -> Result<Cow<'a, str>, DecodeError> {
+    let start = *idx;
+    let len = get_len(bytes, idx)?;
+    let content = take(bytes, idx, len)?;
+    if let Err(e) = std::str::from_utf8(content) {
+        warnings.push(Warning { offset: start, kind: WarningKind::LossyUtf8 { position: e.valid_up_to() } });
+    }
+    Ok(String::from_utf8_lossy(content))
 }
 
-fn get_f64(bytes: &[u8], idx: &mut usize) -> f64 {
-    *idx += 8;
-    f64::from_be_bytes(to_b8(&bytes[*idx - 7..=*idx]))
+/// Decodes items starting at `idx` up to a break byte, and advances `idx` past the break.
+/// This is how the contents of indefinite-length arrays, maps and strings are read.
+pub fn decode_until_break(bytes: &[u8], idx: &mut usize) -> Result<Vec<MajorType>, DecodeError> {
+    items_until_break(bytes, idx, *idx, &mut State::default())
 }
 
-fn get_int(bytes: &[u8], i: &mut usize) -> Option<u64> {
-    let additional = bytes[*i] & 0b00011111;
-    if additional < 24 {
-        *i += 1;
-        Some(additional as u64)
+/// Like [`decode_until_break`], for the indefinite-length item starting at `start`.
+fn items_until_break(bytes: &[u8], idx: &mut usize, start: usize, state: &mut State) -> Result<Vec<MajorType>, DecodeError> {
+    let mut items = Vec::new();
+    while !at_break(bytes, idx)? {
+        let item = decode_item(bytes, idx, state)?;
+        state.grow(&mut items, start)?;
+        items.push(item);
+    }
+    Ok(items)
+}
+
+/// Checks that the item at `offset` can be a chunk of an indefinite-length string of `major_type`:
+/// a definite-length string of the same type.
+fn check_chunk(bytes: &[u8], offset: usize, major_type: u8) -> Result<(), DecodeError> {
+    let initial = bytes[offset];
+    let indefinite = initial & 0b00011111 == 31;
+    if initial >> 5 == major_type && !indefinite {
+        return Ok(());
+    }
+    let found = match initial >> 5 {
+        2 if indefinite => "indefinite-length byte string",
+        3 if indefinite => "indefinite-length text string",
+        4 if indefinite => "indefinite-length array",
+        5 if indefinite => "indefinite-length map",
+        found => validate::major_type_name(found),
+    };
+    Err(DecodeError::InvalidChunk { offset, expected: validate::major_type_name(major_type), found })
+}
+
+/// Joins the chunks of an indefinite-length byte string (major type 2) or text string (3).
+/// A chunk that is not a string of the same type makes the whole string `Invalid`.
+fn join_chunks(major_type: u8, chunks: Vec<MajorType>) -> MajorType {
+    let mut joined = Vec::new();
+    for chunk in chunks {
+        match (major_type, chunk) {
+            (2, BStr(bytes)) => joined.extend(bytes),
+            (3, Str(text)) => joined.extend(text.as_bytes()),
+            _ => return Invalid,
+        }
+    }
+    if major_type == 2 {
+        BStr(joined)
     } else {
-        if additional < 28 {
-            let nbytes = 1 << (additional - 24);
+        // every chunk was valid UTF-8 on its own, so the concatenation is too
+        Str(String::from_utf8(joined).unwrap().into())
+    }
+}
 
-            let int_val = u64::from_be_bytes(to_b8(&bytes[*i + 1..=*i + nbytes]));
-            *i += nbytes + 1;
-            Some(int_val)
-        } else {
-            None
+/// Decodes the item at the start of `bytes`, which must be followed by exactly `trailer_len` bytes
+/// of something else, such as a checksum. Returns the item and those trailing bytes.
+pub fn decode_with_trailer(bytes: &[u8], trailer_len: usize) -> Result<(MajorType, &[u8]), DecodeError> {
+    let (item, end) = decode_at_offset(bytes, 0)?;
+    let trailer = &bytes[end..];
+    if trailer.len() < trailer_len {
+        Err(DecodeError::Truncated { offset: bytes.len() })
+    } else if trailer.len() > trailer_len {
+        Err(DecodeError::TrailingBytes { offset: end + trailer_len })
+    } else {
+        Ok((item, trailer))
+    }
+}
+
+/// Decodes `bytes` as exactly one item, failing with [`DecodeError::TrailingBytes`] if anything follows it.
+pub fn decode_single(bytes: &[u8]) -> Result<MajorType, DecodeError> {
+    decode_with_trailer(bytes, 0).map(|(item, _)| item)
+}
+
+/// Whether `bytes` are any number of well-formed items, with nothing incomplete at the end.
+/// This only skips over the items, without building them, so text strings are not checked to be UTF-8.
+pub fn is_well_formed(bytes: &[u8]) -> bool {
+    let mut idx = 0;
+    while idx < bytes.len() {
+        if skip_item(bytes, &mut idx, true).is_err() {
+            return false;
         }
     }
+    true
 }
 
-#[derive(Debug)]
+/// Whether `bytes` are exactly one well-formed item, as [`decode_single`] would accept.
+pub fn is_well_formed_single(bytes: &[u8]) -> bool {
+    decode_single(bytes).is_ok()
+}
+
+/// Reads a length with [`get_int`], failing if it is too large to address on this platform.
+fn get_len(bytes: &[u8], idx: &mut usize) -> Result<usize, DecodeError> {
+    let offset = *idx;
+    usize::try_from(get_int(bytes, idx)?).map_err(|_| DecodeError::LengthTooLarge { offset })
+}
+
+/// Advances `idx` past exactly one data item without decoding it,
+/// including containers and strings of indefinite length.
+pub fn skip(bytes: &[u8], idx: &mut usize) -> Result<(), DecodeError> {
+    skip_item(bytes, idx, false)
+}
+
+/// Like [`skip`]. If `strict`, it also checks the chunks of indefinite-length strings and that
+/// indefinite-length maps do not end after a key, so that it fails wherever decoding would,
+/// except that the content of text strings is not checked to be UTF-8.
+fn skip_item(bytes: &[u8], idx: &mut usize, strict: bool) -> Result<(), DecodeError> {
+    let start = *idx;
+    let initial = *bytes.get(start).ok_or(DecodeError::Truncated { offset: start })?;
+    let major_type = initial >> 5;
+    if initial == 0xff {
+        return Err(DecodeError::UnexpectedBreak { offset: start });
+    }
+    if initial & 0b00011111 == 31 && (2..=5).contains(&major_type) {
+        *idx += 1;
+        while !at_break(bytes, idx)? {
+            let item = *idx;
+            if strict && (major_type == 2 || major_type == 3) {
+                check_chunk(bytes, item, major_type)?;
+            }
+            skip_item(bytes, idx, strict)?;
+            if strict && major_type == 5 {
+                if at_break(bytes, idx)? {
+                    // the key is only decoded for the error
+                    let key = decode_at(bytes, &mut item.clone()).unwrap_or(Invalid);
+                    return Err(DecodeError::MissingMapValue { offset: *idx - 1, key });
+                }
+                skip_item(bytes, idx, strict)?;
+            }
+        }
+        return Ok(());
+    }
+    match major_type {
+        2 | 3 => {
+            let len = get_len(bytes, idx)?;
+            take(bytes, idx, len)?;
+        }
+        4 | 5 => {
+            let len = get_int(bytes, idx)?;
+            let items = if major_type == 5 { len.saturating_mul(2) } else { len };
+            for _ in 0..items {
+                skip_item(bytes, idx, strict)?;
+            }
+        }
+        6 => {
+            get_tag_number(bytes, idx)?;
+            skip_item(bytes, idx, strict)?;
+        }
+        _ => {
+            decode_at(bytes, idx)?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns the number of bytes taken by the item starting at `offset`.
+pub fn item_len(bytes: &[u8], offset: usize) -> Result<usize, DecodeError> {
+    let mut idx = offset;
+    skip(bytes, &mut idx)?;
+    Ok(idx - offset)
+}
+
+/// Decodes only the top-level item at index `n`, skipping over the items before it.
+/// Returns `None` if there are `n` items or fewer.
+pub fn nth_item(bytes: &[u8], n: usize) -> Result<Option<MajorType>, DecodeError> {
+    let mut idx = 0;
+    for _ in 0..n {
+        if idx == bytes.len() {
+            return Ok(None);
+        }
+        skip(bytes, &mut idx)?;
+    }
+    if idx == bytes.len() {
+        return Ok(None);
+    }
+    decode_at(bytes, &mut idx).map(Some)
+}
+
+/// Counts the top-level items, skipping over each without decoding it.
+/// Fails as [`skip`] does if the last item is incomplete.
+pub fn count_items(bytes: &[u8]) -> Result<usize, DecodeError> {
+    let mut idx = 0;
+    let mut count = 0;
+    while idx < bytes.len() {
+        skip(bytes, &mut idx)?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Returns the bytes of each top-level item, found with [`item_len`] without decoding any of them.
+pub fn split_items(bytes: &[u8]) -> Result<Vec<&[u8]>, DecodeError> {
+    let mut items = Vec::new();
+    let mut idx = 0;
+    while idx < bytes.len() {
+        let len = item_len(bytes, idx)?;
+        items.push(&bytes[idx..idx + len]);
+        idx += len;
+    }
+    Ok(items)
+}
+
+/// Consumes the break byte if it is next, as at the end of an indefinite-length item.
+fn at_break(bytes: &[u8], idx: &mut usize) -> Result<bool, DecodeError> {
+    match bytes.get(*idx) {
+        Some(0xff) => {
+            *idx += 1;
+            Ok(true)
+        }
+        Some(_) => Ok(false),
+        None => Err(DecodeError::Truncated { offset: *idx }),
+    }
+}
+
+/// Returns the next `len` bytes and advances `idx` past them.
+fn take<'a>(bytes: &'a [u8], idx: &mut usize, len: usize) -> Result<&'a [u8], DecodeError> {
+    let slice = idx.checked_add(len)
+        .and_then(|end| bytes.get(*idx..end))
+        .ok_or(DecodeError::Truncated { offset: *idx })?;
+    *idx += len;
+    Ok(slice)
+}
+
+fn get_f16(bytes: &[u8]) -> f32 {
+    let half = u16::from_be_bytes([bytes[0], bytes[1]]);
+    let sign = if half & 0x8000 == 0 { 1.0_f32 } else { -1.0_f32 };
+    let exponent = ((half >> 10) & 0b11111) as i32;
+    let fraction = (half & 0b1111111111) as f32;
+    let magnitude = match exponent {
+        0 => 2.0_f32.powi(-24) * fraction,
+        31 if fraction == 0.0 => f32::INFINITY,
+        // a NaN keeps its sign and payload, so that its half precision bits can be told from it
+        31 => return f32::from_bits(((half & 0x8000) as u32) << 16 | 0x7f80_0000 | ((half & 0x3ff) as u32) << 13),
+        _ => 2.0_f32.powi(exponent - 15) * (1.0_f32 + fraction / 1024_f32),
+    };
+    magnitude * sign
+}
+
+fn get_int(bytes: &[u8], i: &mut usize) -> Result<u64, DecodeError> {
+    let start = *i;
+    let additional = bytes[start] & 0b00011111;
+    *i += 1;
+    if additional < 24 {
+        Ok(additional as u64)
+    } else if additional < 28 {
+        let nbytes = 1 << (additional - 24);
+        Ok(u64::from_be_bytes(to_b8(take(bytes, i, nbytes)?)))
+    } else {
+        *i = start;
+        Err(DecodeError::Reserved { offset: start })
+    }
+}
+
+/// Reads the number of the tag at `idx`. A number that the input ends in the middle of is reported
+/// at the tag, as the number is part of the tag's header rather than an item of its own.
+fn get_tag_number(bytes: &[u8], idx: &mut usize) -> Result<u64, DecodeError> {
+    let start = *idx;
+    get_int(bytes, idx).map_err(|e| match e {
+        DecodeError::Truncated { .. } => DecodeError::Truncated { offset: start },
+        e => e,
+    })
+}
+
+#[derive(Debug, Clone, PartialEq)]
 #[repr(u8)]
-enum MajorType {
+pub enum MajorType {
     U(u64),
     N(i128),
     BStr(Vec<u8>),
-    Str(String),
+    Str(Arc<str>),
     Arr(Vec<MajorType>),
-    Map(HashMap<String, MajorType>),
-    Tag,
+    Map(Vec<(MajorType, MajorType)>),
+    Tag(u64, Box<MajorType>),
     False,
     True,
     Null,
     Undefined,
+    Simple(u8),
     F16(f32),
     F32(f32),
     F64(f64),
     Invalid,
 }
 
+/// Integer accessors. Major types 0 and 1 cover -2^64 ..= 2^64 - 1, so every plain integer fits
+/// an `i128`, but only values up to `i64::MAX` (2^63 - 1) fit an `i64`: `U(9223372036854775808)`
+/// is the first value for which [`MajorType::as_i64`] returns `None`. Bignums (tags 2 and 3)
+/// are accepted by the 128-bit accessors as long as their value fits.
+impl MajorType {
+    /// The RFC 8949 name of the major type this item is encoded with, e.g. `"byte string"`.
+    /// `Invalid` has no major type and is called `"invalid"`.
+    pub fn type_name(&self) -> &'static str {
+        validate::major_type_name(match self {
+            U(_) => 0,
+            N(_) => 1,
+            BStr(_) => 2,
+            Str(_) => 3,
+            Arr(_) => 4,
+            Map(_) => 5,
+            Tag(..) => 6,
+            False | True | Null | Undefined | Simple(_) | F16(_) | F32(_) | F64(_) => 7,
+            Invalid => return "invalid",
+        })
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            U(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        self.as_i128().and_then(|v| i64::try_from(v).ok())
+    }
+
+    pub fn as_i128(&self) -> Option<i128> {
+        match self {
+            U(v) => Some(*v as i128),
+            N(v) => Some(*v),
+            Tag(2, content) => bignum(content).and_then(|v| i128::try_from(v).ok()),
+            Tag(3, content) => bignum(content).and_then(|v| i128::try_from(v).ok()).map(|v| -1 - v),
+            _ => None,
+        }
+    }
+
+    pub fn as_u128(&self) -> Option<u128> {
+        match self {
+            U(v) => Some(*v as u128),
+            Tag(2, content) => bignum(content),
+            _ => None,
+        }
+    }
+}
+
+/// The magnitude of a bignum's byte string content, if it fits a `u128`.
+fn bignum(content: &MajorType) -> Option<u128> {
+    match content {
+        BStr(bytes) => {
+            let significant = &bytes[bytes.iter().take_while(|&&b| b == 0).count()..];
+            (significant.len() <= 16)
+                .then(|| significant.iter().fold(0_u128, |acc, &b| (acc << 8) | b as u128))
+        }
+        _ => None,
+    }
+}
+
+/// Why decoding stopped. Every offset is an absolute position in the input.
+#[derive(Debug)]
+pub enum DecodeError {
+    /// The input ended before the item starting or continuing at `offset` was complete.
+    Truncated { offset: usize },
+    /// The text string whose header is at `offset` is not valid UTF-8. The first invalid byte is
+    /// `position` bytes into the string's content.
+    InvalidUtf8 { offset: usize, position: usize },
+    /// The header at `offset` uses a reserved additional-information value.
+    Reserved { offset: usize },
+    /// The item at `offset` is nested deeper than allowed.
+    DepthExceeded { offset: usize },
+    /// The length in the header at `offset` does not fit in a `usize`.
+    LengthTooLarge { offset: usize },
+    /// A break byte at `offset` where a data item was expected.
+    UnexpectedBreak { offset: usize },
+    /// Input continues at `offset` where it should have ended.
+    TrailingBytes { offset: usize },
+    /// The chunk at `offset` of an indefinite-length string is not a definite-length string of the
+    /// same type, e.g. `expected` is `"text string"` and `found` is `"indefinite-length text string"`.
+    /// The rendering functions show such a string as `Invalid` instead.
+    InvalidChunk { offset: usize, expected: &'static str, found: &'static str },
+    /// The indefinite-length map ends with the break at `offset` right after `key`, so the key has
+    /// no value. The rendering functions give the key the value `Undefined` instead.
+    MissingMapValue { offset: usize, key: MajorType },
+    /// The allocation hook refused `size` bytes for the item starting at `offset`.
+    AllocationDenied { offset: usize, size: usize },
+    /// The header at `offset` is past the number of steps decoding was allowed.
+    BudgetExceeded { offset: usize },
+    /// The content of the tag `tag` at `offset` does not meet the constraint registered for it, for `reason`.
+    TagContentInvalid { offset: usize, tag: u64, reason: String },
+    /// The item at `offset` has type `found` where `expected` was asked for, e.g. `expected` is
+    /// `"unsigned integer"` and `found` is `"text string"`.
+    TypeMismatch { offset: usize, expected: &'static str, found: &'static str },
+    /// The string at `offset` declares a length of `len` bytes, more than the `limit` allowed.
+    StringTooLong { offset: usize, len: usize, limit: usize },
+    /// The item at `path`, `item` in diagnostic notation, has no JSON representation, such as a NaN
+    /// where JSON output is not allowed to write anything else for it.
+    NotJson { path: Path, item: String },
+    /// Merging maps met a map on one side and something else on the other, at `path`.
+    MergeConflict { path: Path },
+    /// Reading the input failed.
+    Io(std::io::Error),
+}
+
+/// I/O errors compare equal when their kinds do.
+impl PartialEq for DecodeError {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (DecodeError::Truncated { offset: a }, DecodeError::Truncated { offset: b })
+            | (DecodeError::Reserved { offset: a }, DecodeError::Reserved { offset: b })
+            | (DecodeError::DepthExceeded { offset: a }, DecodeError::DepthExceeded { offset: b })
+            | (DecodeError::LengthTooLarge { offset: a }, DecodeError::LengthTooLarge { offset: b })
+            | (DecodeError::UnexpectedBreak { offset: a }, DecodeError::UnexpectedBreak { offset: b })
+            | (DecodeError::TrailingBytes { offset: a }, DecodeError::TrailingBytes { offset: b })
+            | (DecodeError::BudgetExceeded { offset: a }, DecodeError::BudgetExceeded { offset: b }) => a == b,
+            (DecodeError::InvalidUtf8 { offset: a, position: p }, DecodeError::InvalidUtf8 { offset: b, position: q }) => {
+                a == b && p == q
+            }
+            (DecodeError::InvalidChunk { offset: a, expected: e, found: f },
+                DecodeError::InvalidChunk { offset: b, expected: g, found: h }) => a == b && e == g && f == h,
+            (DecodeError::MissingMapValue { offset: a, key: k }, DecodeError::MissingMapValue { offset: b, key: l }) => {
+                a == b && k == l
+            }
+            (DecodeError::AllocationDenied { offset: a, size: p }, DecodeError::AllocationDenied { offset: b, size: q }) => {
+                a == b && p == q
+            }
+            (DecodeError::TagContentInvalid { offset: a, tag: t, reason: r },
+                DecodeError::TagContentInvalid { offset: b, tag: u, reason: s }) => a == b && t == u && r == s,
+            (DecodeError::TypeMismatch { offset: a, expected: e, found: f },
+                DecodeError::TypeMismatch { offset: b, expected: g, found: h }) => a == b && e == g && f == h,
+            (DecodeError::StringTooLong { offset: a, len: p, limit: l },
+                DecodeError::StringTooLong { offset: b, len: q, limit: m }) => a == b && p == q && l == m,
+            (DecodeError::MergeConflict { path: a }, DecodeError::MergeConflict { path: b }) => a == b,
+            (DecodeError::NotJson { path: a, item: i }, DecodeError::NotJson { path: b, item: j }) => a == b && i == j,
+            (DecodeError::Io(a), DecodeError::Io(b)) => a.kind() == b.kind(),
+            _ => false,
+        }
+    }
+}
+
+impl DecodeError {
+    /// The position in the input the error was found at, if there is one.
+    pub fn offset(&self) -> Option<usize> {
+        match self {
+            DecodeError::Truncated { offset }
+            | DecodeError::InvalidUtf8 { offset, .. }
+            | DecodeError::Reserved { offset }
+            | DecodeError::DepthExceeded { offset }
+            | DecodeError::LengthTooLarge { offset }
+            | DecodeError::UnexpectedBreak { offset }
+            | DecodeError::TrailingBytes { offset }
+            | DecodeError::InvalidChunk { offset, .. }
+            | DecodeError::MissingMapValue { offset, .. }
+            | DecodeError::AllocationDenied { offset, .. }
+            | DecodeError::BudgetExceeded { offset }
+            | DecodeError::TagContentInvalid { offset, .. }
+            | DecodeError::TypeMismatch { offset, .. }
+            | DecodeError::StringTooLong { offset, .. } => Some(*offset),
+            DecodeError::NotJson { .. } | DecodeError::MergeConflict { .. } | DecodeError::Io(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::Truncated { offset } => write!(f, "input ends before the item at offset {} is complete", offset),
+            DecodeError::InvalidUtf8 { offset, position } => {
+                write!(f, "text string at offset {} is not valid UTF-8 from byte {} of its content on", offset, position)
+            }
+            DecodeError::Reserved { offset } => write!(f, "reserved additional information value at offset {}", offset),
+            DecodeError::DepthExceeded { offset } => write!(f, "item at offset {} is nested too deeply", offset),
+            DecodeError::LengthTooLarge { offset } => write!(f, "length at offset {} is too large", offset),
+            DecodeError::UnexpectedBreak { offset } => write!(f, "unexpected break at offset {}", offset),
+            DecodeError::TrailingBytes { offset } => write!(f, "unexpected bytes after the item, at offset {}", offset),
+            DecodeError::InvalidChunk { offset, expected, found } => {
+                write!(f, "chunk at offset {} has type {}, where a definite-length {} was expected", offset, found, expected)
+            }
+            DecodeError::MissingMapValue { offset, key } => {
+                write!(f, "map key {} has no value before the break at offset {}", key, offset)
+            }
+            DecodeError::AllocationDenied { offset, size } => {
+                write!(f, "allocation of {} bytes for the item at offset {} was refused", size, offset)
+            }
+            DecodeError::BudgetExceeded { offset } => write!(f, "step budget exhausted at offset {}", offset),
+            DecodeError::TagContentInvalid { offset, tag, reason } => {
+                write!(f, "unexpected content for tag {} at offset {}: {}", tag, offset, reason)
+            }
+            DecodeError::TypeMismatch { offset, expected, found } => {
+                write!(f, "item at offset {} has type {}, where {} was expected", offset, found, expected)
+            }
+            DecodeError::StringTooLong { offset, len, limit } => {
+                write!(f, "string at offset {} is {} bytes long, more than the limit of {}", offset, len, limit)
+            }
+            DecodeError::NotJson { path, item } => write!(f, "{} at {} cannot be written as JSON", item, path),
+            DecodeError::MergeConflict { path } => write!(f, "cannot merge a map with something that is not a map at {}", path),
+            DecodeError::Io(e) => write!(f, "reading the input failed: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DecodeError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Moves the offset of `e` by `base`, as it was found in a buffer that starts `base` bytes into the stream.
+fn offset_by(e: DecodeError, base: usize) -> DecodeError {
+    match e {
+        DecodeError::Truncated { offset } => DecodeError::Truncated { offset: base + offset },
+        DecodeError::InvalidUtf8 { offset, position } => DecodeError::InvalidUtf8 { offset: base + offset, position },
+        DecodeError::Reserved { offset } => DecodeError::Reserved { offset: base + offset },
+        DecodeError::DepthExceeded { offset } => DecodeError::DepthExceeded { offset: base + offset },
+        DecodeError::LengthTooLarge { offset } => DecodeError::LengthTooLarge { offset: base + offset },
+        DecodeError::UnexpectedBreak { offset } => DecodeError::UnexpectedBreak { offset: base + offset },
+        DecodeError::TrailingBytes { offset } => DecodeError::TrailingBytes { offset: base + offset },
+        DecodeError::InvalidChunk { offset, expected, found } => DecodeError::InvalidChunk { offset: base + offset, expected, found },
+        DecodeError::MissingMapValue { offset, key } => DecodeError::MissingMapValue { offset: base + offset, key },
+        DecodeError::AllocationDenied { offset, size } => DecodeError::AllocationDenied { offset: base + offset, size },
+        DecodeError::BudgetExceeded { offset } => DecodeError::BudgetExceeded { offset: base + offset },
+        DecodeError::TagContentInvalid { offset, tag, reason } => DecodeError::TagContentInvalid { offset: base + offset, tag, reason },
+        DecodeError::TypeMismatch { offset, expected, found } => DecodeError::TypeMismatch { offset: base + offset, expected, found },
+        DecodeError::StringTooLong { offset, len, limit } => DecodeError::StringTooLong { offset: base + offset, len, limit },
+        DecodeError::NotJson { path, item } => DecodeError::NotJson { path, item },
+        DecodeError::MergeConflict { path } => DecodeError::MergeConflict { path },
+        DecodeError::Io(e) => DecodeError::Io(e),
+    }
+}
+
 fn to_b8(bytes: &[u8]) -> [u8; 8] {
     let mut out = [0_u8; 8];
     for (i, b) in bytes.iter().enumerate() {
@@ -167,11 +1042,71 @@ mod test {
         assert_eq!(format!("[N({})]", i64::MIN), decode(&to_vec(i64::MIN).unwrap()));
     }
 
+    #[test]
+    fn int_i64_boundary() {
+        assert_eq!(Some(i64::MAX), U(9223372036854775807).as_i64());
+        assert_eq!(None, U(9223372036854775808).as_i64());
+        assert_eq!(Some(9223372036854775808), U(9223372036854775808).as_u64());
+        assert_eq!(Some(9223372036854775808), U(9223372036854775808).as_i128());
+        assert_eq!(Some(i64::MIN), N(-9223372036854775808).as_i64());
+        assert_eq!(None, N(-9223372036854775809).as_i64());
+        assert_eq!(None, N(-1).as_u64());
+    }
+
+    #[test]
+    fn int_u64_boundary() {
+        assert_eq!(Some(u64::MAX as i128), U(u64::MAX).as_i128());
+        assert_eq!(Some(u64::MAX as u128), U(u64::MAX).as_u128());
+        assert_eq!(Some(-18446744073709551616), N(-18446744073709551616).as_i128());
+        assert_eq!(None, N(-18446744073709551616).as_u128());
+    }
+
+    #[test]
+    fn bignum_i128_boundary() {
+        let bignum = |bytes: Vec<u8>| Tag(2, Box::new(BStr(bytes)));
+        // 2^64 is just past what major type 0 can hold
+        assert_eq!(Some(1 << 64), bignum(vec![1, 0, 0, 0, 0, 0, 0, 0, 0]).as_i128());
+        assert_eq!(Some(i128::MAX), bignum([vec![0x7f], vec![0xff; 15]].concat()).as_i128());
+        assert_eq!(None, bignum([vec![0x80], vec![0; 15]].concat()).as_i128());
+        assert_eq!(Some(1 << 127), bignum([vec![0x80], vec![0; 15]].concat()).as_u128());
+        assert_eq!(Some(u128::MAX), bignum([vec![0; 2], vec![0xff; 16]].concat()).as_u128());
+        assert_eq!(None, bignum([vec![1], vec![0; 16]].concat()).as_u128());
+        assert_eq!(Some(i128::MIN), Tag(3, Box::new(BStr([vec![0x7f], vec![0xff; 15]].concat()))).as_i128());
+    }
+
+    #[test]
+    fn neg_int_most_negative_everywhere() {
+        let bytes = [0x3b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff];
+        let most_negative = N(-18446744073709551616);
+        assert_eq!(Ok((most_negative.clone(), 9)), decode_at_offset(&bytes, 0));
+        assert_eq!("[N(-18446744073709551616)]", decode(&bytes));
+        assert_eq!("[\n  N(-18446744073709551616),\n]", decode_pretty(&bytes, &DecodeOptions::default()));
+        assert_eq!("-18446744073709551616", decode_diag(&bytes));
+        assert_eq!(". = N(-18446744073709551616)", decode_flat(&bytes));
+        assert!(decode_dot(&bytes).contains("N(-18446744073709551616)"));
+        assert_eq!(Some(Value::Int(-18446744073709551616)), most_negative.to_value());
+        assert_eq!(Some(-18446744073709551616), most_negative.as_i128());
+        assert_eq!(None, most_negative.as_i64());
+        assert_eq!(Some(bytes.to_vec()), encode(&most_negative));
+    }
+
     #[test]
     fn float16() {
         assert_eq!("[F16(1.0009766)]", decode(&[249, 60, 1]));
     }
 
+    #[test]
+    fn float16_special() {
+        assert_eq!("[F16(5.9604645e-8), F16(-0.0), F16(inf), F16(NaN)]",
+                   decode(&[0xf9, 0x00, 0x01, 0xf9, 0x80, 0x00, 0xf9, 0x7c, 0x00, 0xf9, 0x7e, 0x00]));
+    }
+
+    #[test]
+    fn negative_zero() {
+        assert_eq!("[F16(-0.0), F32(-0.0), F64(-0.0), F64(0.0)]",
+                   decode(&[0xf9, 0x80, 0x00, 0xfa, 0x80, 0, 0, 0, 0xfb, 0x80, 0, 0, 0, 0, 0, 0, 0, 0xfb, 0, 0, 0, 0, 0, 0, 0, 0]));
+    }
+
     #[test]
     fn float32() {
         assert_eq!("[F32(1.0)]", decode(&to_vec(1.0_f32).unwrap()));
@@ -182,6 +1117,31 @@ mod test {
         assert_eq!("[F64(10088000023.10022)]", decode(&to_vec(10088000023.10022_f64).unwrap()));
     }
 
+    #[test]
+    fn simple_short() {
+        assert_eq!("[Simple(16)]", decode(&[0xf0]));
+    }
+
+    #[test]
+    fn simple_one_byte() {
+        assert_eq!(Ok((Simple(32), 2)), decode_at_offset(&[0xf8, 0x20], 0));
+    }
+
+    #[test]
+    fn simple_one_byte_reserved() {
+        assert_eq!(Err(DecodeError::Reserved { offset: 0 }), decode_at_offset(&[0xf8, 0x1f], 0));
+    }
+
+    #[test]
+    fn simple_additional_reserved() {
+        for initial in [0xfc, 0xfd, 0xfe] {
+            assert_eq!(Err(DecodeError::Reserved { offset: 1 }), decode_at_offset(&[0x81, initial, 0x00], 0));
+            assert_eq!(Err(DecodeError::Reserved { offset: 1 }), count_items(&[0x01, initial]));
+            assert!(!is_well_formed_single(&[initial]));
+            assert_eq!("[U(1), Invalid]", decode(&[0x01, initial, 0x02]));
+        }
+    }
+
     #[test]
     fn bytestring() {
         assert_eq!(format!("[BStr([1, 2, 3, 4, 5])]"), decode(&[0b01000101, 1, 2, 3, 4, 5]));
@@ -232,6 +1192,380 @@ mod test {
             ).unwrap()));
     }
 
+    #[test]
+    fn decode_at_offset_after_header() {
+        let mut bytes = vec![0xde, 0xad, 0xbe, 0xef];
+        bytes.extend(to_vec("Hello").unwrap());
+        bytes.push(0x01);
+        assert_eq!(Ok((Str("Hello".into()), 10)), decode_at_offset(&bytes, 4));
+        assert_eq!(Ok((U(1), 11)), decode_at_offset(&bytes, 10));
+    }
+
+    #[test]
+    fn truncated_tag_number() {
+        // a tag with an 8-byte number of which 3 bytes are there, and [a 2-byte one with 1]
+        for (bytes, offset) in [(&[0xdb, 0x01, 0x02, 0x03][..], 0), (&[0x81, 0xd9, 0x01][..], 1)] {
+            assert_eq!(Err(DecodeError::Truncated { offset }), decode_single(bytes));
+            assert_eq!(Err(DecodeError::Truncated { offset }), count_items(bytes));
+            assert_eq!(Err(DecodeError::Truncated { offset }), decode_iterative(bytes, 0).map(|_| ()));
+        }
+        assert_eq!("[Invalid]", decode(&[0xdb, 0x01, 0x02, 0x03]));
+    }
+
+    #[test]
+    fn decode_at_offset_reports_absolute_offset() {
+        // 4 byte header, then a byte string declaring 5 bytes with only 2 present
+        let bytes = [0xde, 0xad, 0xbe, 0xef, 0b01000101, 1, 2];
+        assert_eq!(Err(DecodeError::Truncated { offset: 5 }), decode_at_offset(&bytes, 4));
+    }
+
+    #[test]
+    fn tag_unregistered() {
+        assert_eq!("[Tag(1234, U(21))]", decode(&[0xd9, 0x04, 0xd2, 0x15]));
+    }
+
+    #[test]
+    fn tag_registered_handler() {
+        let mut tags = TagRegistry::new();
+        tags.register_tag(1234, |content| match content {
+            U(celsius) => Some(format!("Celsius({})", celsius)),
+            _ => None,
+        });
+        assert_eq!("[Arr([Celsius(21), Tag(1234, Str(\"warm\"))])]",
+                   decode_with_tags(&[0x82, 0xd9, 0x04, 0xd2, 0x15, 0xd9, 0x04, 0xd2, 0x64, b'w', b'a', b'r', b'm'], &tags));
+    }
+
+    #[test]
+    fn map() {
+        assert_eq!("[Map({Str(\"b\"): U(1), U(2): Arr([])})]", decode(&[0xa2, 0x61, b'b', 0x01, 0x02, 0x80]));
+    }
+
+    #[test]
+    fn map_with_dictionary() {
+        let dictionary = HashMap::from([(1, "unit".to_string()), (2, "temperature".to_string())]);
+        assert_eq!("[Map({(\"unit\"=1): Str(\"C\"), (\"temperature\"=2): U(21), U(3): U(4)})]",
+                   decode_with_dictionary(&[0xa3, 0x01, 0x61, b'C', 0x02, 0x15, 0x03, 0x04], &dictionary));
+    }
+
+    #[test]
+    fn map_with_key_normalizer() {
+        let normalize = |key: &MajorType| match key {
+            U(1) => "name".to_string(),
+            Str(name) => name.to_string(),
+            key => key.to_string(),
+        };
+        // v1 {1: "x", 2: [{1: "y"}]} and v2 {"name": "x"}
+        let v1 = decode_with_key_normalizer(&[0xa2, 0x01, 0x61, b'x', 0x02, 0x81, 0xa1, 0x01, 0x61, b'y'], normalize);
+        assert_eq!("[Map({Str(\"name\"): Str(\"x\"), Str(\"2\"): Arr([Map({Str(\"name\"): Str(\"y\")})])})]", v1);
+        let v2 = decode_with_key_normalizer(&[0xa1, 0x64, b'n', b'a', b'm', b'e', 0x61, b'x'], normalize);
+        assert_eq!("[Map({Str(\"name\"): Str(\"x\")})]", v2);
+    }
+
+    #[test]
+    fn until_break() {
+        let bytes = [0x01, 0x61, b'a', 0x80, 0xff, 0x02];
+        let mut idx = 0;
+        assert_eq!(Ok(vec![U(1), Str("a".into()), Arr(vec![])]), decode_until_break(&bytes, &mut idx));
+        assert_eq!(5, idx);
+    }
+
+    #[test]
+    fn until_break_missing() {
+        assert_eq!(Err(DecodeError::Truncated { offset: 2 }), decode_until_break(&[0x01, 0x02], &mut 0));
+    }
+
+    #[test]
+    fn indefinite_containers() {
+        // [_ 1, {_ "a": 2}], (_ h'01' h'0203'), (_ "he" "llo")
+        let bytes = [0x9f, 0x01, 0xbf, 0x61, b'a', 0x02, 0xff, 0xff, 0x5f, 0x41, 0x01, 0x42, 0x02, 0x03, 0xff,
+            0x7f, 0x62, b'h', b'e', 0x63, b'l', b'l', b'o', 0xff];
+        assert_eq!("[Arr([U(1), Map({Str(\"a\"): U(2)})]), BStr([1, 2, 3]), Str(\"hello\")]", decode(&bytes));
+    }
+
+    #[test]
+    fn indefinite_string_wrong_chunk() {
+        assert_eq!("[Invalid]", decode(&[0x7f, 0x41, 0x01, 0xff]));
+    }
+
+    #[test]
+    fn indefinite_map_odd_items() {
+        // {_ 1: 2, "a"}, 3
+        let bytes = [0xbf, 0x01, 0x02, 0x61, b'a', 0xff, 0x03];
+        assert_eq!(Err(DecodeError::MissingMapValue { offset: 5, key: Str("a".into()) }), decode_at_offset(&bytes, 0));
+        assert_eq!("[Map({U(1): U(2), Str(\"a\"): Undefined}), U(3)]", decode(&bytes));
+    }
+
+    #[test]
+    fn unexpected_break() {
+        assert_eq!(Err(DecodeError::UnexpectedBreak { offset: 1 }), decode_at_offset(&[0x81, 0xff], 0));
+    }
+
+    #[test]
+    fn trailer_after_map() {
+        let bytes = [0xa1, 0x01, 0x02, 0xde, 0xad, 0xbe, 0xef];
+        assert_eq!(Ok((Map(vec![(U(1), U(2))]), &bytes[3..])), decode_with_trailer(&bytes, 4));
+        assert_eq!(Err(DecodeError::Truncated { offset: 7 }), decode_with_trailer(&bytes, 5));
+        assert_eq!(Err(DecodeError::TrailingBytes { offset: 6 }), decode_with_trailer(&bytes, 3));
+    }
+
+    #[test]
+    fn skip_to_third_item() {
+        // [_ 1, 2], (_ "a" "b"), 3(h'01'), "third"
+        let bytes = [0x9f, 0x01, 0x02, 0xff, 0x7f, 0x61, b'a', 0x61, b'b', 0xff, 0xc3, 0x41, 0x01,
+            0x65, b't', b'h', b'i', b'r', b'd'];
+        let mut idx = 0;
+        skip(&bytes, &mut idx).unwrap();
+        assert_eq!(4, idx);
+        skip(&bytes, &mut idx).unwrap();
+        skip(&bytes, &mut idx).unwrap();
+        assert_eq!(Ok((Str("third".into()), bytes.len())), decode_at_offset(&bytes, idx));
+    }
+
+    #[test]
+    fn nth_of_five() {
+        // the second item is not valid UTF-8, so it would fail if it were decoded
+        let bytes = [0x01, 0x62, 0xc3, 0x28, 0x83, 0x01, 0x02, 0x03, 0xf6, 0x40];
+        assert_eq!(Ok(Some(Arr(vec![U(1), U(2), U(3)]))), nth_item(&bytes, 2));
+        assert_eq!(Ok(Some(BStr(vec![]))), nth_item(&bytes, 4));
+        assert_eq!(Ok(None), nth_item(&bytes, 5));
+        assert_eq!(Err(DecodeError::InvalidUtf8 { offset: 1, position: 0 }), nth_item(&bytes, 1));
+    }
+
+    #[test]
+    fn count_top_level_items() {
+        // 1, [2, {3: 4}], "a"
+        let bytes = [0x01, 0x82, 0x02, 0xa1, 0x03, 0x04, 0x61, b'a'];
+        assert_eq!(Ok(3), count_items(&bytes));
+        assert_eq!(Ok(0), count_items(&[]));
+        assert_eq!(Err(DecodeError::Truncated { offset: 5 }), count_items(&bytes[..5]));
+    }
+
+    #[test]
+    fn split_top_level_items() {
+        // 1, [2, {3: 4}], "a"
+        let bytes = [0x01, 0x82, 0x02, 0xa1, 0x03, 0x04, 0x61, b'a'];
+        let items = split_items(&bytes).unwrap();
+        assert_eq!(vec![&bytes[..1], &bytes[1..6], &bytes[6..]], items);
+        let decoded = items.iter().map(|item| decode_single(item)).collect::<Result<Vec<_>, _>>();
+        assert_eq!(Ok(vec![U(1), Arr(vec![U(2), Map(vec![(U(3), U(4))])]), Str("a".into())]), decoded);
+        assert_eq!(Err(DecodeError::Truncated { offset: 7 }), split_items(&bytes[..7]));
+    }
+
+    #[test]
+    fn largest_declared_lengths() {
+        // an array, a map and a byte string claiming u64::MAX elements, entries and bytes
+        for initial in [0x9b, 0xbb, 0x5b] {
+            let mut bytes = vec![initial];
+            bytes.extend([0xff; 8]);
+            bytes.push(0x00);
+            let expected = if initial == 0x5b { 9 } else { 10 };
+            let mut largest = 0;
+            let result = decode_with_allocation_hook(&bytes, |size| {
+                largest = largest.max(size);
+                true
+            });
+            assert_eq!(Err(DecodeError::Truncated { offset: expected }), result);
+            assert!(largest <= 4 * std::mem::size_of::<MajorType>());
+            assert_eq!(Err(DecodeError::Truncated { offset: expected }), decode_single(&bytes));
+            assert_eq!(Err(DecodeError::Truncated { offset: expected }), count_items(&bytes));
+            assert_eq!(Err(DecodeError::Truncated { offset: expected }), decode_iterative(&bytes, 0).map(|_| ()));
+            assert_eq!("[Invalid]", decode(&bytes));
+        }
+    }
+
+    #[test]
+    fn error_messages() {
+        let errors = [
+            (DecodeError::Truncated { offset: 1 }, "input ends before the item at offset 1 is complete"),
+            (DecodeError::InvalidUtf8 { offset: 2, position: 3 }, "text string at offset 2 is not valid UTF-8 from byte 3 of its content on"),
+            (DecodeError::Reserved { offset: 3 }, "reserved additional information value at offset 3"),
+            (DecodeError::DepthExceeded { offset: 4 }, "item at offset 4 is nested too deeply"),
+            (DecodeError::LengthTooLarge { offset: 5 }, "length at offset 5 is too large"),
+            (DecodeError::UnexpectedBreak { offset: 6 }, "unexpected break at offset 6"),
+            (DecodeError::TrailingBytes { offset: 7 }, "unexpected bytes after the item, at offset 7"),
+            (DecodeError::InvalidChunk { offset: 8, expected: "text string", found: "byte string" },
+             "chunk at offset 8 has type byte string, where a definite-length text string was expected"),
+            (DecodeError::MissingMapValue { offset: 9, key: Str("a".into()) }, "map key \"a\" has no value before the break at offset 9"),
+            (DecodeError::AllocationDenied { offset: 10, size: 12 }, "allocation of 12 bytes for the item at offset 10 was refused"),
+            (DecodeError::BudgetExceeded { offset: 11 }, "step budget exhausted at offset 11"),
+            (DecodeError::TagContentInvalid { offset: 12, tag: 52, reason: "too long".to_string() },
+             "unexpected content for tag 52 at offset 12: too long"),
+            (DecodeError::TypeMismatch { offset: 13, expected: "text string", found: "map" }, "item at offset 13 has type map, where text string was expected"),
+            (DecodeError::StringTooLong { offset: 14, len: 100, limit: 10 }, "string at offset 14 is 100 bytes long, more than the limit of 10"),
+        ];
+        for (i, (error, message)) in errors.iter().enumerate() {
+            assert_eq!(*message, error.to_string());
+            assert_eq!(Some(i + 1), error.offset());
+        }
+        let io = DecodeError::Io(std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "eof"));
+        assert_eq!("reading the input failed: eof", io.to_string());
+        assert_eq!(None, io.offset());
+        assert!(std::error::Error::source(&io).is_some());
+    }
+
+    #[test]
+    fn propagates_as_error() {
+        fn first(bytes: &[u8]) -> Result<MajorType, Box<dyn std::error::Error>> {
+            Ok(decode_single(bytes)?)
+        }
+        assert_eq!("unexpected break at offset 0", first(&[0xff]).unwrap_err().to_string());
+    }
+
+    #[test]
+    fn small_integer_arrays() {
+        // every single-byte integer and the two-byte ones around them, also nested
+        let items: Vec<MajorType> = (0..30).map(U).chain((-30..0).map(N)).collect();
+        let bytes = encode(&Arr(vec![Arr(items.clone()), U(1), Arr(vec![])])).unwrap();
+        let expected = Arr(vec![Arr(items), U(1), Arr(vec![])]);
+        assert_eq!(Ok(expected.clone()), decode_single(&bytes));
+        // limits take the general path
+        assert_eq!(Ok(vec![expected.clone()]), decode_with_step_budget(&bytes, 1000));
+        assert_eq!(Ok((vec![expected], 2)), decode_with_depth(&bytes));
+        assert_eq!(Err(DecodeError::BudgetExceeded { offset: 6 }), decode_with_step_budget(&bytes, 5));
+        assert_eq!(Err(DecodeError::DepthExceeded { offset: 3 }), decode_with_max_depth(&bytes, 1));
+    }
+
+    #[test]
+    fn max_depth() {
+        // [[1]], 1(2)
+        let bytes = [0x81, 0x81, 0x01, 0xc1, 0x02];
+        assert_eq!(Err(DecodeError::DepthExceeded { offset: 2 }), decode_with_max_depth(&bytes, 1));
+        assert_eq!(Ok(vec![Arr(vec![Arr(vec![U(1)])]), Tag(1, Box::new(U(2)))]), decode_with_max_depth(&bytes, 2));
+        assert_eq!(Err(DecodeError::DepthExceeded { offset: 1 }), decode_with_max_depth(&bytes[3..], 0));
+    }
+
+    #[test]
+    fn depth_reached() {
+        // [1, {"a": [2(h'')]}], "b", (_ h'01')
+        let bytes = [0x82, 0x01, 0xa1, 0x61, b'a', 0x81, 0xc2, 0x40, 0x61, b'b', 0x5f, 0x41, 0x01, 0xff];
+        let (items, depth) = decode_with_depth(&bytes).unwrap();
+        assert_eq!(3, items.len());
+        assert_eq!(4, depth);
+        assert!(decode_with_max_depth(&bytes, depth).is_ok());
+        assert!(decode_with_max_depth(&bytes, depth - 1).is_err());
+        assert_eq!(Ok((vec![U(1)], 0)), decode_with_depth(&[0x01]));
+    }
+
+    #[test]
+    fn step_budget() {
+        // [[[]], [[]], (_ "a" "b")]
+        let bytes = [0x83, 0x81, 0x80, 0x81, 0x80, 0x7f, 0x61, b'a', 0x61, b'b', 0xff];
+        assert_eq!(Err(DecodeError::BudgetExceeded { offset: 3 }), decode_with_step_budget(&bytes, 3));
+        // the chunks count as steps of their own
+        assert_eq!(Err(DecodeError::BudgetExceeded { offset: 8 }), decode_with_step_budget(&bytes, 7));
+        assert!(decode_with_step_budget(&bytes, 8).is_ok());
+        // a long array of small items is stopped after a bounded amount of work
+        let mut long = vec![0x9a, 0x00, 0x0f, 0x42, 0x40];
+        long.resize(5 + 1_000_000, 0x00);
+        assert_eq!(Err(DecodeError::BudgetExceeded { offset: 1004 }), decode_with_step_budget(&long, 1000));
+    }
+
+    #[test]
+    fn max_string_len() {
+        let options = DecodeOptions { max_string_len: Some(16), ..Default::default() };
+        // [h'0102', h'...'] declaring 2 GB that are not there: rejected before its content is looked for
+        let bytes = [0x82, 0x42, 0x01, 0x02, 0x5a, 0x80, 0x00, 0x00, 0x00];
+        let error = DecodeError::StringTooLong { offset: 4, len: 0x8000_0000, limit: 16 };
+        assert_eq!(Err(error), decode_json(&bytes, &options));
+        assert_eq!(Err(DecodeError::Truncated { offset: 9 }), decode_json(&bytes, &DecodeOptions::default()));
+        assert_eq!("[Invalid]", decode_with_options(&bytes, &options));
+        // (_ "0123456789" "0123456789") is longer than its chunks
+        let mut bytes = vec![0x7f, 0x6a];
+        bytes.extend(b"0123456789");
+        bytes.push(0x6a);
+        bytes.extend(b"0123456789");
+        bytes.push(0xff);
+        assert_eq!(Err(DecodeError::StringTooLong { offset: 0, len: 20, limit: 16 }), decode_json(&bytes, &options));
+        assert_eq!(Ok("\"0123456789\"".to_string()), decode_json(&bytes[1..12], &options));
+    }
+
+    #[test]
+    fn auto_binary_or_hex() {
+        assert_eq!(Ok("[Arr([U(1), U(2)])]".to_string()), decode_auto(&[0x82, 0x01, 0x02]));
+        assert_eq!(Ok("[Arr([U(1), U(2)])]".to_string()), decode_auto(b"82 01 02\n"));
+        assert_eq!(Ok("[Arr([U(1), U(2)])]".to_string()), decode_auto(b"820102"));
+        assert_eq!(Err(DecodeError::Truncated { offset: 2 }), decode_auto(b"8201"));
+        // also the CBOR of -17, -17
+        assert_eq!(Ok("[U(0)]".to_string()), decode_auto(b"00"));
+        assert_eq!(Ok("[]".to_string()), decode_auto(b""));
+    }
+
+    #[test]
+    fn well_formed_single() {
+        assert!(is_well_formed_single(&[0x82, 0x01, 0x61, b'a']));
+        assert!(!is_well_formed_single(&[0x82, 0x01, 0x61, b'a', 0x00]));
+        assert!(!is_well_formed_single(&[0x82, 0x01]));
+        assert!(!is_well_formed_single(&[]));
+        assert_eq!(Err(DecodeError::TrailingBytes { offset: 1 }), decode_single(&[0x01, 0x02]));
+    }
+
+    #[test]
+    fn well_formed() {
+        // 1, [_ "a"], {_ 1: (_ h'01')}, and none at all
+        assert!(is_well_formed(&[0x01, 0x9f, 0x61, b'a', 0xff, 0xbf, 0x01, 0x5f, 0x41, 0x01, 0xff, 0xff]));
+        assert!(is_well_formed(&[]));
+        assert!(!is_well_formed(&[0x01, 0x82, 0x01]));
+        // reserved additional information, a two-byte simple value below 32 and a lone break
+        assert!(!is_well_formed(&[0x1c]));
+        assert!(!is_well_formed(&[0xfd]));
+        assert!(!is_well_formed(&[0xf8, 0x1f]));
+        assert!(!is_well_formed(&[0x01, 0xff]));
+        // a chunk of the wrong type and a map key without a value
+        assert!(!is_well_formed(&[0x5f, 0x61, b'a', 0xff]));
+        assert!(!is_well_formed(&[0xbf, 0x01, 0xff]));
+        // skipping alone lets the missing value pass
+        assert_eq!(Ok(3), item_len(&[0xbf, 0x01, 0xff], 0));
+    }
+
+    #[test]
+    fn wrong_type_chunk() {
+        // (_ "a", h'00')
+        let bytes = [0x7f, 0x61, b'a', 0x41, 0x00, 0xff];
+        let expected = DecodeError::InvalidChunk { offset: 3, expected: "text string", found: "byte string" };
+        assert_eq!(Err(expected), decode_at_offset(&bytes, 0));
+        assert_eq!("[Invalid]", decode(&bytes));
+    }
+
+    #[test]
+    fn nested_indefinite_chunk() {
+        // (_ (_ h'01'))
+        let bytes = [0x5f, 0x5f, 0x41, 0x01, 0xff, 0xff];
+        let expected = || DecodeError::InvalidChunk { offset: 1, expected: "byte string", found: "indefinite-length byte string" };
+        assert_eq!(Err(expected()), decode_at_offset(&bytes, 0));
+        assert_eq!(Err(expected()), decode_iterative(&bytes, 0));
+        assert_eq!("[Invalid]", decode(&bytes));
+    }
+
+    #[test]
+    fn type_names() {
+        let items = [U(0), N(-1), BStr(vec![]), Str("".into()), Arr(vec![]), Map(vec![]), Tag(1, Box::new(U(0))),
+            False, True, Null, Undefined, Simple(0), F16(0.0), F32(0.0), F64(0.0), Invalid];
+        let names = ["unsigned integer", "negative integer", "byte string", "text string", "array", "map", "tag",
+            "simple/float", "simple/float", "simple/float", "simple/float", "simple/float", "simple/float",
+            "simple/float", "simple/float", "invalid"];
+        assert_eq!(names.to_vec(), items.iter().map(MajorType::type_name).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn invalid_utf8_position() {
+        // [0, "aaa...a\xff"] with 300 valid bytes before the invalid one
+        let mut bytes = vec![0x82, 0x00, 0x79, 0x01, 0x2d];
+        bytes.extend([b'a'; 300]);
+        bytes.push(0xff);
+        assert_eq!(Err(DecodeError::InvalidUtf8 { offset: 2, position: 300 }), decode_at_offset(&bytes, 0));
+    }
+
+    #[test]
+    fn skip_indefinite_map() {
+        assert_eq!(Ok(6), item_len(&[0xbf, 0x01, 0x9f, 0xff, 0xf6, 0xff], 0));
+    }
+
+    #[test]
+    fn skip_truncated() {
+        assert_eq!(Err(DecodeError::Truncated { offset: 3 }), item_len(&[0x83, 0x01, 0x02], 0));
+        assert_eq!(Err(DecodeError::UnexpectedBreak { offset: 1 }), item_len(&[0x81, 0xff], 0));
+    }
+
     #[derive(Decode, Encode)]
     enum Simple {
         #[n(1)] Left(#[n(1)] String),
@@ -0,0 +1,70 @@
+use crate::MajorType::{self, *};
+use crate::{decode_single, encode_with_options, DecodeError, EncodeOptions, Path, Segment};
+
+/// Decodes `base` and `overlay`, each a single map, and encodes the map with the entries of both.
+/// Where both have a key, the value from `overlay` wins, except that two maps are merged in turn.
+/// Fails with [`DecodeError::MergeConflict`] where one side has a map and the other does not,
+/// including when either input is not a map.
+///
+/// Entries keep their order: those of `base` come first, followed by the keys only `overlay` has.
+pub fn merge(base: &[u8], overlay: &[u8]) -> Result<Vec<u8>, DecodeError> {
+    let mut merged = decode_single(base)?;
+    merge_into(&mut merged, decode_single(overlay)?, &Path::default())?;
+    let options = EncodeOptions { preserve_order: true, ..Default::default() };
+    Ok(encode_with_options(&merged, &options).expect("decoded items can be encoded"))
+}
+
+fn merge_into(base: &mut MajorType, overlay: MajorType, path: &Path) -> Result<(), DecodeError> {
+    let (Map(base_entries), Map(overlay_entries)) = (base, overlay) else {
+        return Err(DecodeError::MergeConflict { path: path.clone() });
+    };
+    for (key, value) in overlay_entries {
+        match base_entries.iter_mut().find(|(base_key, _)| *base_key == key) {
+            Some((_, base_value)) => match (&*base_value, &value) {
+                (Map(_), _) | (_, Map(_)) => merge_into(base_value, value, &path.child(Segment::Key(key)))?,
+                _ => *base_value = value,
+            },
+            None => base_entries.push((key, value)),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn map(entries: Vec<(&str, MajorType)>) -> MajorType {
+        Map(entries.into_iter().map(|(key, value)| (Str(key.into()), value)).collect())
+    }
+
+    fn encode(item: &MajorType) -> Option<Vec<u8>> {
+        encode_with_options(item, &EncodeOptions { preserve_order: true, ..Default::default() })
+    }
+
+    #[test]
+    fn overlay_wins_and_nested_maps_merge() {
+        let base = map(vec![
+            ("name", Str("server".into())),
+            ("net", map(vec![("port", U(80)), ("host", Str("localhost".into()))])),
+        ]);
+        let overlay = map(vec![("net", map(vec![("port", U(8080)), ("tls", True)])), ("debug", True)]);
+        let merged = merge(&encode(&base).unwrap(), &encode(&overlay).unwrap()).unwrap();
+        let expected = map(vec![
+            ("name", Str("server".into())),
+            ("net", map(vec![("port", U(8080)), ("host", Str("localhost".into())), ("tls", True)])),
+            ("debug", True),
+        ]);
+        assert_eq!(Ok(expected), decode_single(&merged));
+    }
+
+    #[test]
+    fn conflicts() {
+        let base = encode(&map(vec![("net", map(vec![("port", U(80))]))])).unwrap();
+        let overlay = encode(&map(vec![("net", U(1))])).unwrap();
+        let conflict = merge(&base, &overlay).unwrap_err();
+        assert_eq!(DecodeError::MergeConflict { path: Path(vec![Segment::Key(Str("net".into()))]) }, conflict);
+        assert_eq!("cannot merge a map with something that is not a map at net", conflict.to_string());
+        assert_eq!(Err(DecodeError::MergeConflict { path: Path::default() }), merge(&[0x80], &base));
+    }
+}
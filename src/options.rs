@@ -0,0 +1,164 @@
+/// Settings for the rendering functions that take options, such as [`decode_with_options`](crate::decode_with_options).
+/// Construct with `..Default::default()` so that options added later keep their defaults.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodeOptions {
+    /// What one level of indentation consists of in pretty output.
+    pub indent: Indent,
+    /// The width that output which is not pretty stays within where it can: containers that would
+    /// make a line longer go on separate lines as in pretty output, while those that fit stay on one.
+    pub max_line_width: Option<usize>,
+    /// Whether pretty output puts the opening bracket of a container on its own line.
+    pub bracket_on_new_line: bool,
+    /// Whether byte strings that are valid UTF-8 render as text, e.g. `BStr(b"Hi")`.
+    pub bytes_as_text_if_utf8: bool,
+    /// How the contents of byte strings are written, by default as an array of decimal numbers.
+    pub bytes_format: BytesFormat,
+    /// Whether floats are followed by their big-endian encoding, e.g. `F32(1.0 [0x3f, 0x80, 0x00, 0x00])`.
+    pub show_float_bytes: bool,
+    /// Whether floats are followed by their class, one of `Normal`, `Subnormal`, `Zero`, `Infinite`
+    /// and `NaN`, e.g. `F64(0.0 [Zero])`. Half floats are classed as half floats.
+    pub classify_floats: bool,
+    /// Whether tags without a handler show their IANA registered name, e.g. `Tag(1 /epoch-date-time/, U(0))`.
+    pub annotate_tag_names: bool,
+    /// Whether diagnostic notation from [`decode_diag_with_options`](crate::decode_diag_with_options)
+    /// follows integers, strings, floats and booleans with their type, e.g. `1 /uint/` or `h'01' /bstr/`,
+    /// and tags with their IANA registered name, e.g. `1(0 /uint/) /epoch-date-time/`.
+    pub annotate_types: bool,
+    /// Whether the value of map key 1, the COSE header label for the algorithm, shows the registered
+    /// algorithm name, e.g. `N(-7 /ES256/)`. Meant for COSE headers, where every such key is `alg`.
+    pub cose_algorithm_names: bool,
+    /// Whether COSE messages under tags 16 to 18 and 96 to 98 show their fields by name, with the
+    /// protected headers decoded, e.g. `CoseSign1({protected: Map({U(1): N(-7)}), ...})`. The
+    /// signatures and recipients nested in them show the same way.
+    pub cose_structures: bool,
+    /// The number of elements shown of longer arrays, which end with e.g. `...(1000 elements total)`.
+    pub max_array_display: Option<usize>,
+    /// Whether runs of equal consecutive array elements show once with their count, e.g. `U(0) ×5000`.
+    pub fold_repeated_elements: bool,
+    /// Whether text under tag 21, 22 or 23 (expected base64url, base64 or base16) shows as the bytes it
+    /// encodes, e.g. `Tag(23, BStr([1, 2]))` for `Tag(23, Str("0102"))`. Text that does not decode stays text.
+    pub decode_expected_encodings: bool,
+    /// Whether JSON output writes integers and bignums as decimal text, e.g. `"9007199254740993"`,
+    /// so that consumers that read numbers as doubles do not lose precision. Map keys are written as
+    /// they are without it: bare in JSON5 output, as their text in JSON output.
+    pub integers_as_strings: bool,
+    /// How JSON output writes NaN and infinite floats, which JSON has no numbers for. By default
+    /// [`decode_json`](crate::decode_json) fails on them, while JSON5 output writes them as the
+    /// JSON5 literals `NaN`, `Infinity` and `-Infinity`.
+    pub json_nonfinite: JsonNonFinite,
+    /// How [`decode_json`](crate::decode_json) writes map keys that are not text strings, which
+    /// JSON object keys must be. JSON5 output leaves integer keys bare and writes the others as
+    /// their diagnostic notation, whatever this says.
+    pub json_key_mode: JsonKeyMode,
+    /// Whether the elements of sets (arrays under tag 258) show sorted by their deterministic encoding,
+    /// so that equal sets look the same whatever order they were encoded in.
+    pub sort_sets: bool,
+    /// The base integers are written in. In hexadecimal, negative integers keep their sign rather
+    /// than show as two's complement, e.g. `N(-0x10)`.
+    pub int_radix: Radix,
+    /// The longest text or byte string, in bytes, that is decoded. A longer one is rejected by its
+    /// declared length before its content is read: [`decode_json`](crate::decode_json) fails with
+    /// [`DecodeError::StringTooLong`](crate::DecodeError::StringTooLong), while the rendering
+    /// functions end with `Invalid` there. For indefinite-length strings the chunks count together.
+    pub max_string_len: Option<usize>,
+}
+
+impl Default for DecodeOptions {
+    fn default() -> Self {
+        DecodeOptions {
+            indent: Indent::Spaces(2),
+            max_line_width: None,
+            bracket_on_new_line: false,
+            bytes_as_text_if_utf8: false,
+            bytes_format: BytesFormat::DecimalArray,
+            show_float_bytes: false,
+            classify_floats: false,
+            annotate_tag_names: false,
+            annotate_types: false,
+            cose_algorithm_names: false,
+            cose_structures: false,
+            max_array_display: None,
+            fold_repeated_elements: false,
+            decode_expected_encodings: false,
+            integers_as_strings: false,
+            json_nonfinite: JsonNonFinite::Error,
+            json_key_mode: JsonKeyMode::Diagnostic,
+            sort_sets: false,
+            int_radix: Radix::Dec,
+            max_string_len: None,
+        }
+    }
+}
+
+/// One level of indentation: a number of spaces or tabs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Indent {
+    Spaces(usize),
+    Tabs(usize),
+}
+
+impl Indent {
+    pub(crate) fn write(&self, depth: usize, out: &mut String) {
+        let (unit, width) = match self {
+            Indent::Spaces(width) => (' ', width),
+            Indent::Tabs(width) => ('\t', width),
+        };
+        out.extend(std::iter::repeat_n(unit, width * depth));
+    }
+}
+
+/// What JSON output makes of a NaN or infinite float.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JsonNonFinite {
+    /// Fail with [`DecodeError::NotJson`](crate::DecodeError::NotJson).
+    Error,
+    /// Write `null`, as RFC 8949 section 6.1 suggests.
+    Null,
+    /// Write the name as text: `"NaN"`, `"Infinity"` or `"-Infinity"`.
+    String,
+}
+
+/// How JSON output turns a map key that is not a text string into one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JsonKeyMode {
+    /// The key in diagnostic notation, e.g. `"1"`, `"h'01'"` or `"[1, 2]"`. The default.
+    /// In this mode and the others, a key can clash with a text key that reads the same, such as `1` and `"1"`.
+    Diagnostic,
+    /// The key converted as a value would be, taken as text if it is not already, e.g. `"1"`, `"AQ"`
+    /// for the bytes `01` in base64url, or `"[1, 2]"`.
+    Converted,
+    /// As `Converted`, but failing with [`DecodeError::NotJson`](crate::DecodeError::NotJson) on
+    /// array and map keys, which have no sensible text form.
+    Strict,
+}
+
+/// The base integers are rendered in: decimal, e.g. `U(255)`, or hexadecimal, e.g. `U(0xff)`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Radix {
+    Dec,
+    Hex,
+}
+
+/// How byte strings are rendered: `BStr([1, 2, 3])`, or as in diagnostic notation `BStr(h'010203')`
+/// for hex and `BStr(b64'AQID')` for either base64 alphabet.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BytesFormat {
+    DecimalArray,
+    Hex,
+    /// The standard alphabet of RFC 4648, with padding.
+    Base64,
+    /// The URL and filename safe alphabet of RFC 4648, without padding.
+    Base64Url,
+}
+
+/// Settings for [`encode_with_options`](crate::encode_with_options). The defaults give the
+/// deterministic encoding of RFC 8949 section 4.2.1.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EncodeOptions {
+    /// Whether `F16`, `F32` and `F64` keep their width instead of taking the shortest exact one,
+    /// so that re-encoded floats match the bytes they were decoded from.
+    pub preserve_float_width: bool,
+    /// Whether map entries keep the order they are in instead of being sorted by key, so that maps
+    /// decoded from non-canonical input, such as signed maps, re-encode to the same bytes.
+    pub preserve_order: bool,
+}
@@ -0,0 +1,259 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
+
+use crate::MajorType::{self, *};
+use crate::{decode_items, encode, DecodeOptions, TagRegistry};
+use crate::render::Renderer;
+
+/// The location of a node in a decoded item, as the sequence of steps from the root.
+///
+/// Displayed as `headers.alg`, `payload[0]` or `claims[1]`: text keys that look like identifiers
+/// follow a dot, all other map keys appear in diagnostic notation between brackets, as do array
+/// indices. The root itself displays as `.`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Path(pub Vec<Segment>);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Segment {
+    Index(usize),
+    Key(MajorType),
+}
+
+impl Path {
+    pub(crate) fn child(&self, segment: Segment) -> Path {
+        let mut segments = self.0.clone();
+        segments.push(segment);
+        Path(segments)
+    }
+}
+
+impl Display for Path {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.0.is_empty() {
+            return f.write_str(".");
+        }
+        for (i, segment) in self.0.iter().enumerate() {
+            match segment {
+                Segment::Index(index) => write!(f, "[{}]", index)?,
+                Segment::Key(Str(key)) if is_identifier(key) => {
+                    if i > 0 {
+                        f.write_str(".")?;
+                    }
+                    f.write_str(key)?
+                }
+                Segment::Key(key) => write!(f, "[{}]", key)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+fn is_identifier(key: &str) -> bool {
+    key.chars().next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && key.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+}
+
+/// Lists every leaf value of `bytes` on its own line as `path = value`, in document order.
+/// Leaves are scalars, tags and empty containers. Each top-level item is flattened on its own.
+pub fn decode_flat(bytes: &[u8]) -> String {
+    let tags = TagRegistry::new();
+    let options = DecodeOptions::default();
+    let renderer = Renderer::new(&tags, &options);
+    let mut lines = Vec::new();
+    for item in decode_items(bytes) {
+        flatten(&item, Path::default(), &mut |path, leaf| lines.push(format!("{} = {}", path, renderer.render_one(leaf))));
+    }
+    lines.join("\n")
+}
+
+/// Returns every leaf value of `bytes` with its path, both as text, the value in diagnostic notation.
+/// The leaves of each top-level item are sorted by path, with map keys in the order deterministic
+/// encoding sorts them in, so that equal maps give the same list whatever order their entries are
+/// encoded in, as snapshot tests want. Maps in the content of a tag keep their order.
+pub fn flatten_sorted(bytes: &[u8]) -> Vec<(String, String)> {
+    let mut pairs = Vec::new();
+    for item in decode_items(bytes) {
+        let mut leaves = Vec::new();
+        flatten(&item, Path::default(), &mut |path, leaf| leaves.push((path.clone(), leaf.to_string())));
+        leaves.sort_by(|(a, _), (b, _)| compare_paths(a, b));
+        pairs.extend(leaves.into_iter().map(|(path, value)| (path.to_string(), value)));
+    }
+    pairs
+}
+
+/// Orders paths segment by segment, array indices before map keys and map keys by their encoding.
+fn compare_paths(a: &Path, b: &Path) -> Ordering {
+    let segment = |a: &Segment, b: &Segment| match (a, b) {
+        (Segment::Index(a), Segment::Index(b)) => a.cmp(b),
+        (Segment::Index(_), Segment::Key(_)) => Ordering::Less,
+        (Segment::Key(_), Segment::Index(_)) => Ordering::Greater,
+        (Segment::Key(a), Segment::Key(b)) => encode(a).cmp(&encode(b)),
+    };
+    a.0.iter().zip(&b.0)
+        .map(|(a, b)| segment(a, b))
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or_else(|| a.0.len().cmp(&b.0.len()))
+}
+
+/// Calls `visit` with every leaf under `item` and its path.
+fn flatten(item: &MajorType, path: Path, visit: &mut impl FnMut(&Path, &MajorType)) {
+    match item {
+        Arr(items) if !items.is_empty() => {
+            for (i, item) in items.iter().enumerate() {
+                flatten(item, path.child(Segment::Index(i)), visit);
+            }
+        }
+        Map(entries) if !entries.is_empty() => {
+            for (key, value) in entries {
+                flatten(value, path.child(Segment::Key(key.clone())), visit);
+            }
+        }
+        leaf => visit(&path, leaf),
+    }
+}
+
+/// Returns the paths of every node in `bytes` that `pred` holds for, in document order.
+/// Containers are tested before their children; the content of a tag shares the path of the tag.
+/// Paths are relative to the top-level item they were found in.
+pub fn find(bytes: &[u8], pred: impl Fn(&MajorType) -> bool) -> Vec<Path> {
+    let mut found = Vec::new();
+    for item in decode_items(bytes) {
+        visit_nodes(&item, Path::default(), &mut |path, node| {
+            if pred(node) {
+                found.push(path.clone());
+            }
+        });
+    }
+    found
+}
+
+/// Decodes all top-level items as the rendering functions do, together with the paths each distinct byte
+/// string value occurs at, to show which ones repeat. Paths are as [`find`] gives them; byte
+/// strings that are map keys have no path of their own and are left out.
+pub fn decode_with_byte_paths(bytes: &[u8]) -> (Vec<MajorType>, HashMap<Vec<u8>, Vec<Path>>) {
+    let items = decode_items(bytes);
+    let mut paths: HashMap<Vec<u8>, Vec<Path>> = HashMap::new();
+    for item in &items {
+        visit_nodes(item, Path::default(), &mut |path, node| {
+            if let BStr(bytes) = node {
+                paths.entry(bytes.clone()).or_default().push(path.clone());
+            }
+        });
+    }
+    (items, paths)
+}
+
+/// Calls `visit` with every node under `item` and its path, containers before their children.
+fn visit_nodes(item: &MajorType, path: Path, visit: &mut impl FnMut(&Path, &MajorType)) {
+    visit(&path, item);
+    match item {
+        Arr(items) => {
+            for (i, item) in items.iter().enumerate() {
+                visit_nodes(item, path.child(Segment::Index(i)), visit);
+            }
+        }
+        Map(entries) => {
+            for (key, value) in entries {
+                visit_nodes(value, path.child(Segment::Key(key.clone())), visit);
+            }
+        }
+        Tag(_, content) => visit_nodes(content, path, visit),
+        _ => {}
+    }
+}
+
+/// Returns every byte string in `bytes`, however deeply nested, in document order.
+/// Map keys come before their values.
+pub fn collect_bytes(bytes: &[u8]) -> Vec<Vec<u8>> {
+    let mut found = Vec::new();
+    for item in decode_items(bytes) {
+        collect_bytes_in(item, &mut found);
+    }
+    found
+}
+
+fn collect_bytes_in(item: MajorType, found: &mut Vec<Vec<u8>>) {
+    match item {
+        BStr(bytes) => found.push(bytes),
+        Arr(items) => items.into_iter().for_each(|item| collect_bytes_in(item, found)),
+        Map(entries) => entries.into_iter().for_each(|(key, value)| {
+            collect_bytes_in(key, found);
+            collect_bytes_in(value, found);
+        }),
+        Tag(_, content) => collect_bytes_in(*content, found),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn sorted_regardless_of_key_order() {
+        // {"b": [1, {}], 10: "x", "a": {"y": 2, "x": 1.5}} and the same with the entries of both maps reversed
+        let first = [0xa3, 0x61, b'b', 0x82, 0x01, 0xa0, 0x0a, 0x61, b'x', 0x61, b'a', 0xa2, 0x61, b'y', 0x02,
+            0x61, b'x', 0xf9, 0x3e, 0x00];
+        let second = [0xa3, 0x61, b'a', 0xa2, 0x61, b'x', 0xf9, 0x3e, 0x00, 0x61, b'y', 0x02, 0x0a, 0x61, b'x',
+            0x61, b'b', 0x82, 0x01, 0xa0];
+        let pair = |path: &str, value: &str| (path.to_string(), value.to_string());
+        let expected = vec![
+            pair("[10]", "\"x\""),
+            pair("a.x", "1.5"),
+            pair("a.y", "2"),
+            pair("b[0]", "1"),
+            pair("b[1]", "{}"),
+        ];
+        assert_eq!(expected, flatten_sorted(&first));
+        assert_eq!(expected, flatten_sorted(&second));
+        assert_ne!(decode_flat(&first), decode_flat(&second));
+    }
+
+    #[test]
+    fn flat_nested_map() {
+        // {"headers": {"alg": -7, 4: h'01'}, "payload": [1, []], "a b": null}
+        let bytes = [0xa3, 0x67, b'h', b'e', b'a', b'd', b'e', b'r', b's', 0xa2, 0x63, b'a', b'l', b'g', 0x26, 0x04, 0x41, 0x01,
+            0x67, b'p', b'a', b'y', b'l', b'o', b'a', b'd', 0x82, 0x01, 0x80, 0x63, b'a', b' ', b'b', 0xf6];
+        assert_eq!("headers.alg = N(-7)
+headers[4] = BStr([1])
+payload[0] = U(1)
+payload[1] = Arr([])
+[\"a b\"] = Null", decode_flat(&bytes));
+    }
+
+    #[test]
+    fn flat_scalar() {
+        assert_eq!(". = U(1)\n. = Tag(1, U(2))", decode_flat(&[0x01, 0xc1, 0x02]));
+    }
+
+    #[test]
+    fn find_large_integers() {
+        // {"a": [5, 200, {"b": 1000}], "c": 101, "d": 1(300)}
+        let bytes = [0xa3, 0x61, b'a', 0x83, 0x05, 0x18, 0xc8, 0xa1, 0x61, b'b', 0x19, 0x03, 0xe8,
+            0x61, b'c', 0x18, 0x65, 0x61, b'd', 0xc1, 0x19, 0x01, 0x2c];
+        let paths = find(&bytes, |item| item.as_u64().is_some_and(|v| v > 100));
+        let paths = paths.iter().map(|path| path.to_string()).collect::<Vec<_>>();
+        assert_eq!(vec!["a[1]", "a[2].b", "c", "d"], paths);
+    }
+
+    #[test]
+    fn repeated_byte_strings() {
+        // {"key": h'0102', "copies": [h'03', 2(h'0102')], h'0102': 0}
+        let bytes = [0xa3, 0x63, b'k', b'e', b'y', 0x42, 0x01, 0x02, 0x66, b'c', b'o', b'p', b'i', b'e', b's',
+            0x82, 0x41, 0x03, 0xc2, 0x42, 0x01, 0x02, 0x42, 0x01, 0x02, 0x00];
+        let (items, paths) = decode_with_byte_paths(&bytes);
+        assert_eq!(1, items.len());
+        let shown = |content: &[u8]| paths[content].iter().map(|path| path.to_string()).collect::<Vec<_>>();
+        assert_eq!(vec!["key", "copies[1]"], shown(&[1, 2]));
+        assert_eq!(vec!["copies[0]"], shown(&[3]));
+        assert_eq!(2, paths.len());
+    }
+
+    #[test]
+    fn nested_byte_strings() {
+        // h'01', [2(h'02'), {h'03': [h'04']}], "no", h'05'
+        let bytes = [0x41, 0x01, 0x82, 0xc2, 0x41, 0x02, 0xa1, 0x41, 0x03, 0x81, 0x41, 0x04, 0x62, b'n', b'o', 0x41, 0x05];
+        assert_eq!(vec![vec![1], vec![2], vec![3], vec![4], vec![5]], collect_bytes(&bytes));
+    }
+}
@@ -0,0 +1,132 @@
+use crate::MajorType::{self, *};
+use crate::{decode_single, encode_with_options, DecodeError, EncodeOptions};
+
+/// Decodes `bytes` as a single item, replaces the value at each of `paths` with the text
+/// `"[REDACTED]"` and encodes the result, so that it can be logged without the secrets it held.
+///
+/// Paths are written the way [`Path`](crate::Path) displays them: `user.password`, `keys[0]`,
+/// `["api key"]` or `[-1]`. A number between brackets is an index into an array and a key in a map.
+/// Tags are looked through, as their content shares their path. `.` is the item itself.
+/// Paths that lead nowhere, or that cannot be read, are skipped.
+///
+/// Everything else keeps its order and float widths.
+pub fn redact(bytes: &[u8], paths: &[&str]) -> Result<Vec<u8>, DecodeError> {
+    let mut item = decode_single(bytes)?;
+    for path in paths {
+        if let Some(value) = parse(path).and_then(|steps| find_mut(&mut item, &steps)) {
+            *value = Str("[REDACTED]".into());
+        }
+    }
+    let options = EncodeOptions { preserve_float_width: true, preserve_order: true };
+    Ok(encode_with_options(&item, &options).expect("decoded items can be encoded"))
+}
+
+enum Step {
+    Key(MajorType),
+    /// An array index or an integer map key.
+    Number(i128),
+}
+
+fn parse(path: &str) -> Option<Vec<Step>> {
+    if path == "." {
+        return Some(vec![]);
+    }
+    let mut steps = Vec::new();
+    let mut rest = path;
+    while !rest.is_empty() {
+        if let Some(inner) = rest.strip_prefix('[') {
+            let (step, after) = bracketed(inner)?;
+            steps.push(step);
+            rest = after;
+        } else {
+            // a name follows a dot, except at the start
+            let name = if rest.len() == path.len() { rest } else { rest.strip_prefix('.')? };
+            let end = name.find(['.', '[']).unwrap_or(name.len());
+            if end == 0 {
+                return None;
+            }
+            steps.push(Step::Key(Str(name[..end].into())));
+            rest = &name[end..];
+        }
+    }
+    Some(steps)
+}
+
+/// Reads a quoted text key or a number up to the closing bracket, returning what follows it.
+fn bracketed(inner: &str) -> Option<(Step, &str)> {
+    let Some(quoted) = inner.strip_prefix('"') else {
+        let end = inner.find(']')?;
+        return Some((Step::Number(inner[..end].parse().ok()?), &inner[end + 1..]));
+    };
+    let mut text = String::new();
+    let mut chars = quoted.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => text.push(match chars.next()?.1 {
+                'n' => '\n',
+                'r' => '\r',
+                't' => '\t',
+                c => c,
+            }),
+            '"' => return Some((Step::Key(Str(text.into())), quoted[i + 1..].strip_prefix(']')?)),
+            c => text.push(c),
+        }
+    }
+    None
+}
+
+fn find_mut<'a>(item: &'a mut MajorType, steps: &[Step]) -> Option<&'a mut MajorType> {
+    let Some((step, rest)) = steps.split_first() else { return Some(item) };
+    match (item, step) {
+        (Tag(_, content), _) => find_mut(content, steps),
+        (Arr(items), Step::Number(index)) => find_mut(items.get_mut(usize::try_from(*index).ok()?)?, rest),
+        (Map(entries), step) => {
+            let key = match step {
+                Step::Key(key) => key.clone(),
+                Step::Number(v) if *v >= 0 => U(u64::try_from(*v).ok()?),
+                Step::Number(v) => N(*v),
+            };
+            let (_, value) = entries.iter_mut().find(|(k, _)| *k == key)?;
+            find_mut(value, rest)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn encode(item: &MajorType) -> Vec<u8> {
+        encode_with_options(item, &EncodeOptions { preserve_order: true, ..Default::default() }).unwrap()
+    }
+
+    fn user(password: MajorType, token: MajorType) -> MajorType {
+        Map(vec![
+            (Str("user".into()), Map(vec![
+                (Str("name".into()), Str("alice".into())),
+                (Str("password".into()), password),
+                (Str("auth tokens".into()), Arr(vec![Str("t1".into()), token])),
+            ])),
+            (U(2), Tag(24, Box::new(Map(vec![(N(-1), BStr(vec![1, 2]))])))),
+            (Str("id".into()), U(7)),
+        ])
+    }
+
+    #[test]
+    fn nested_values_redacted() {
+        let bytes = encode(&user(Str("secret".into()), Str("t2".into())));
+        let redacted = redact(&bytes, &["user.password", "user[\"auth tokens\"][1]", "[2][-1]", "user.missing", "id[0]", "user..name"]).unwrap();
+        let hidden = || Str("[REDACTED]".into());
+        let mut expected = user(hidden(), hidden());
+        let Map(entries) = &mut expected else { unreachable!() };
+        entries[1].1 = Tag(24, Box::new(Map(vec![(N(-1), hidden())])));
+        assert_eq!(encode(&expected), redacted);
+    }
+
+    #[test]
+    fn whole_item_redacted() {
+        assert_eq!(vec![0x6a, b'[', b'R', b'E', b'D', b'A', b'C', b'T', b'E', b'D', b']'], redact(&[0x01], &["."]).unwrap());
+        assert_eq!(Err(DecodeError::Truncated { offset: 1 }), redact(&[0x81], &["."]));
+    }
+}
@@ -0,0 +1,525 @@
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::num::FpCategory;
+
+use crate::MajorType::{self, *};
+use crate::cose::{cose_algorithm_name, cose_message, cose_nested, protected_headers, CoseStructure};
+use crate::{base64, decode_all, hex, State};
+use crate::encode::{encode, to_f16};
+use crate::options::{BytesFormat, DecodeOptions, Radix};
+use crate::tags::{render_builtin, tag_name, TagRegistry};
+
+/// Renders decoded items the way `{:?}` would, except that tags are offered to the registry first,
+/// URIs, IRIs, extended times and encoded CBOR sequences (tag 63) are shown specially, and integer
+/// map keys found in the dictionary are shown with their name.
+/// Map keys can also be replaced altogether by the text string `normalize_key` makes of them, and
+/// simple values that have a name show it too.
+/// When `pretty` is set, container entries go on separate lines, indented as the options say.
+/// Otherwise that happens only for the containers that do not fit the maximum line width, if set.
+pub(crate) struct Renderer<'a> {
+    pub tags: &'a TagRegistry,
+    pub options: &'a DecodeOptions,
+    pub dictionary: Option<&'a HashMap<u64, String>>,
+    pub normalize_key: Option<&'a dyn Fn(&MajorType) -> String>,
+    pub simple_names: Option<&'a HashMap<u8, String>>,
+    pub pretty: bool,
+    /// Set while a container is rendered on one line to measure it, so that the containers inside
+    /// it do not measure themselves again.
+    pub measuring: Cell<bool>,
+}
+
+impl<'a> Renderer<'a> {
+    pub fn new(tags: &'a TagRegistry, options: &'a DecodeOptions) -> Self {
+        Renderer { tags, options, dictionary: None, normalize_key: None, simple_names: None, pretty: false, measuring: Cell::new(false) }
+    }
+
+    pub fn render(&self, items: &[MajorType]) -> String {
+        let mut out = String::new();
+        self.render_container(("", '[', ']', ""), items, 0, &mut out, |item, depth, out| self.render_item(item, depth, out));
+        out
+    }
+
+    pub fn render_one(&self, item: &MajorType) -> String {
+        let mut out = String::new();
+        self.render_item(item, 0, &mut out);
+        out
+    }
+
+    /// Writes `prefix`, then the entries between `open` and `close`, then `suffix`.
+    fn render_container<T>(&self, (prefix, open, close, suffix): (&str, char, char, &str), entries: &[T], depth: usize,
+                           out: &mut String, render_entry: impl Fn(&T, usize, &mut String)) {
+        let wrap = !entries.is_empty() && (self.pretty || self.overflows((prefix, open, close, suffix), entries, depth, out, &render_entry));
+        out.push_str(prefix);
+        if !wrap {
+            out.push(open);
+            for (i, entry) in entries.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                render_entry(entry, depth, out);
+            }
+            out.push(close);
+        } else {
+            if self.options.bracket_on_new_line && !prefix.is_empty() {
+                self.new_line(depth, out);
+            }
+            out.push(open);
+            for entry in entries {
+                self.new_line(depth + 1, out);
+                render_entry(entry, depth + 1, out);
+                out.push(',');
+            }
+            self.new_line(depth, out);
+            out.push(close);
+        }
+        out.push_str(suffix);
+    }
+
+    /// Whether the container would go past the maximum line width on one line after `out`.
+    fn overflows<T>(&self, parts: (&str, char, char, &str), entries: &[T], depth: usize, out: &str,
+                    render_entry: &dyn Fn(&T, usize, &mut String)) -> bool {
+        let Some(width) = self.options.max_line_width.filter(|_| !self.measuring.get()) else { return false };
+        self.measuring.set(true);
+        let mut line = String::new();
+        self.render_container(parts, entries, depth, &mut line, render_entry);
+        self.measuring.set(false);
+        let column = out[out.rfind('\n').map_or(0, |i| i + 1)..].chars().count();
+        column + line.chars().count() > width
+    }
+
+    fn new_line(&self, depth: usize, out: &mut String) {
+        out.push('\n');
+        self.options.indent.write(depth, out);
+    }
+
+    fn render_item(&self, item: &MajorType, depth: usize, out: &mut String) {
+        match item {
+            Arr(items) => self.render_array(items, depth, out),
+            Map(entries) => self.render_container(("Map(", '{', '}', ")"), entries, depth, out, |(key, value), depth, out| {
+                self.render_key(key, depth, out);
+                out.push_str(": ");
+                let algorithm = match (key, value) {
+                    (U(1), U(_) | N(_)) if self.options.cose_algorithm_names => value.as_i64().and_then(cose_algorithm_name),
+                    _ => None,
+                };
+                match (value, algorithm) {
+                    (U(v), Some(name)) => write!(out, "U({} /{}/)", self.int(*v as i128), name).unwrap(),
+                    (N(v), Some(name)) => write!(out, "N({} /{}/)", self.int(*v), name).unwrap(),
+                    _ => self.render_item(value, depth, out),
+                }
+            }),
+            Tag(number, content) => {
+                let converted = self.convert_content(*number, content);
+                let content = converted.as_ref().unwrap_or(content);
+                if let Some(rendered) = self.tags.render(*number, content).or_else(|| render_builtin(*number, content)) {
+                    out.push_str(&rendered);
+                } else if let Some(items) = embedded_sequence(*number, content) {
+                    self.render_container(("CborSeq(", '[', ']', ")"), &items, depth, out, |item, depth, out| self.render_item(item, depth, out));
+                } else if let (41, Arr(items)) = (*number, content) {
+                    self.render_container(("Array[homogeneous](", '[', ']', ")"), items, depth, out, |item, depth, out| self.render_item(item, depth, out));
+                } else if let Some(message) = cose_message(*number, content).filter(|_| self.options.cose_structures) {
+                    self.render_cose(&message, depth, out);
+                } else {
+                    self.render_tag(*number, content, depth, out);
+                }
+            }
+            U(v) => write!(out, "U({})", self.int(*v as i128)).unwrap(),
+            N(v) => write!(out, "N({})", self.int(*v)).unwrap(),
+            BStr(bytes) => match self.tags.render_bytes(None, bytes) {
+                Some(rendered) => out.push_str(&rendered),
+                None => match std::str::from_utf8(bytes) {
+                    Ok(text) if self.options.bytes_as_text_if_utf8 => write!(out, "BStr(b{:?})", text).unwrap(),
+                    _ => self.render_bytes(bytes, out),
+                },
+            },
+            F16(v) if self.shows_float_details() => {
+                // half floats below 2^-14 are subnormal, although as an f32 they are normal
+                let category = match v.classify() {
+                    FpCategory::Normal if v.abs() < 2.0_f32.powi(-14) => FpCategory::Subnormal,
+                    category => category,
+                };
+                // every half float value, NaNs included, was decoded from the bits it converts back to
+                self.render_float(("F16", format!("{:?}", v)), category, &to_f16(*v).unwrap_or(0x7e00).to_be_bytes(), out);
+            }
+            F32(v) if self.shows_float_details() => self.render_float(("F32", format!("{:?}", v)), v.classify(), &v.to_be_bytes(), out),
+            F64(v) if self.shows_float_details() => self.render_float(("F64", format!("{:?}", v)), v.classify(), &v.to_be_bytes(), out),
+            Simple(v) => match self.simple_names.and_then(|names| names.get(v)) {
+                Some(name) => write!(out, "Simple({} /{}/)", v, name).unwrap(),
+                None => write!(out, "{:?}", item).unwrap(),
+            },
+            scalar => write!(out, "{:?}", scalar).unwrap(),
+        }
+    }
+
+    /// Writes a byte string in the format the options ask for.
+    fn render_bytes(&self, bytes: &[u8], out: &mut String) {
+        match self.options.bytes_format {
+            BytesFormat::DecimalArray => write!(out, "BStr({:?})", bytes).unwrap(),
+            BytesFormat::Hex => {
+                out.push_str("BStr(h'");
+                for b in bytes {
+                    write!(out, "{:02x}", b).unwrap();
+                }
+                out.push_str("')");
+            }
+            BytesFormat::Base64 => write!(out, "BStr(b64'{}')", base64::encode_standard(bytes)).unwrap(),
+            BytesFormat::Base64Url => write!(out, "BStr(b64'{}')", base64::encode_url(bytes)).unwrap(),
+        }
+    }
+
+    /// Writes the generic `Tag(n, content)` form.
+    fn render_tag(&self, number: u64, content: &MajorType, depth: usize, out: &mut String) {
+        write!(out, "Tag({}", number).unwrap();
+        if let Some(name) = tag_name(number).filter(|_| self.options.annotate_tag_names) {
+            write!(out, " /{}/", name).unwrap();
+        }
+        out.push_str(", ");
+        let nested = match content {
+            BStr(bytes) => self.tags.render_bytes(Some(number), bytes),
+            _ => None,
+        };
+        match nested {
+            Some(rendered) => out.push_str(&rendered),
+            None => self.render_item(content, depth, out),
+        }
+        out.push(')');
+    }
+
+    fn render_cose(&self, structure: &CoseStructure, depth: usize, out: &mut String) {
+        let prefix = format!("{}(", structure.name);
+        self.render_container((&prefix, '{', '}', ")"), &structure.fields, depth, out, |&(field, value), depth, out| {
+            write!(out, "{}: ", field).unwrap();
+            match (field, value) {
+                ("protected", value) => match protected_headers(value) {
+                    Some(headers) => self.render_item(&headers, depth, out),
+                    None => self.render_item(value, depth, out),
+                },
+                ("signatures" | "recipients", Arr(items)) => {
+                    self.render_container(("", '[', ']', ""), items, depth, out, |item, depth, out| match cose_nested(field, item) {
+                        Some(nested) => self.render_cose(&nested, depth, out),
+                        None => self.render_item(item, depth, out),
+                    })
+                }
+                (_, value) => self.render_item(value, depth, out),
+            }
+        });
+    }
+
+    fn shows_float_details(&self) -> bool {
+        self.options.show_float_bytes || self.options.classify_floats
+    }
+
+    /// Writes a float variant with the details the options ask for, e.g. `F64(0.0 [Zero])`.
+    fn render_float(&self, (variant, value): (&str, String), category: FpCategory, bytes: &[u8], out: &mut String) {
+        write!(out, "{}({}", variant, value).unwrap();
+        if self.options.classify_floats {
+            let name = match category {
+                FpCategory::Normal => "Normal",
+                FpCategory::Subnormal => "Subnormal",
+                FpCategory::Zero => "Zero",
+                FpCategory::Infinite => "Infinite",
+                FpCategory::Nan => "NaN",
+            };
+            write!(out, " [{}]", name).unwrap();
+        }
+        if self.options.show_float_bytes {
+            out.push(' ');
+            write_hex_list(bytes, out);
+        }
+        out.push(')');
+    }
+
+    /// Formats an integer in the radix the options ask for.
+    fn int(&self, v: i128) -> String {
+        match self.options.int_radix {
+            Radix::Dec => v.to_string(),
+            Radix::Hex if v < 0 => format!("-{:#x}", v.unsigned_abs()),
+            Radix::Hex => format!("{:#x}", v),
+        }
+    }
+
+    /// What the content of a tag shows as instead, if the options ask for that: the bytes encoded by
+    /// text under an expected-encoding tag, or the elements of a set in order of their encoding.
+    fn convert_content(&self, number: u64, content: &MajorType) -> Option<MajorType> {
+        match (number, content) {
+            (21, Str(text)) if self.options.decode_expected_encodings => base64::decode_url(text).map(BStr),
+            (22, Str(text)) if self.options.decode_expected_encodings => base64::decode_standard(text).map(BStr),
+            (23, Str(text)) if self.options.decode_expected_encodings => hex::decode(text).map(BStr),
+            (258, Arr(items)) if self.options.sort_sets => {
+                // elements that cannot be encoded go last
+                let mut keyed = items.iter().map(|item| (encode(item), item)).collect::<Vec<_>>();
+                keyed.sort_by(|(a, _), (b, _)| (a.is_none(), a).cmp(&(b.is_none(), b)));
+                Some(Arr(keyed.into_iter().map(|(_, item)| item.clone()).collect()))
+            }
+            _ => None,
+        }
+    }
+
+    fn render_array(&self, items: &[MajorType], depth: usize, out: &mut String) {
+        let shown = match self.options.max_array_display {
+            Some(max) if items.len() > max => &items[..max],
+            _ => items,
+        };
+        // runs of equal elements with their length, then None for the marker of elements not shown
+        let mut entries: Vec<Option<(&MajorType, usize)>> = Vec::new();
+        for item in shown {
+            match entries.last_mut() {
+                Some(Some((last, count))) if self.options.fold_repeated_elements && *last == item => *count += 1,
+                _ => entries.push(Some((item, 1))),
+            }
+        }
+        if shown.len() < items.len() {
+            entries.push(None);
+        }
+        self.render_container(("Arr(", '[', ']', ")"), &entries, depth, out, |entry, depth, out| match entry {
+            Some((item, 1)) => self.render_item(item, depth, out),
+            Some((item, count)) => {
+                self.render_item(item, depth, out);
+                write!(out, " ×{}", count).unwrap();
+            }
+            None => write!(out, "...({} elements total)", items.len()).unwrap(),
+        })
+    }
+
+    fn render_key(&self, key: &MajorType, depth: usize, out: &mut String) {
+        if let Some(normalize_key) = self.normalize_key {
+            write!(out, "Str({:?})", normalize_key(key)).unwrap();
+            return;
+        }
+        match (key, self.dictionary) {
+            (U(number), Some(dictionary)) if dictionary.contains_key(number) => {
+                write!(out, "({:?}={})", dictionary[number], number).unwrap()
+            }
+            _ => self.render_item(key, depth, out),
+        }
+    }
+}
+
+/// The items of the CBOR sequence encoded in the byte string under tag 63, if it is well-formed.
+fn embedded_sequence(number: u64, content: &MajorType) -> Option<Vec<MajorType>> {
+    match (number, content) {
+        (63, BStr(bytes)) => decode_all(bytes, &mut State::default()).ok(),
+        _ => None,
+    }
+}
+
+/// Writes `bytes` as `[0x01, 0x02]`.
+fn write_hex_list(bytes: &[u8], out: &mut String) {
+    let hex = bytes.iter().map(|b| format!("{:#04x}", b)).collect::<Vec<_>>();
+    write!(out, "[{}]", hex.join(", ")).unwrap();
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{decode, decode_pretty, decode_with_options, BytesFormat, DecodeOptions, Indent, Radix};
+
+    // [1, {"a": []}]
+    const NESTED: [u8; 6] = [0x82, 0x01, 0xa1, 0x61, b'a', 0x80];
+
+    #[test]
+    fn pretty_two_spaces() {
+        assert_eq!("[
+  Arr([
+    U(1),
+    Map({
+      Str(\"a\"): Arr([]),
+    }),
+  ]),
+]", decode_pretty(&NESTED, &DecodeOptions::default()));
+    }
+
+    #[test]
+    fn pretty_four_spaces() {
+        let options = DecodeOptions { indent: Indent::Spaces(4), ..Default::default() };
+        assert_eq!("[
+    Arr([
+        U(1),
+        Map({
+            Str(\"a\"): Arr([]),
+        }),
+    ]),
+]", decode_pretty(&NESTED, &options));
+    }
+
+    #[test]
+    fn pretty_tab_bracket_on_new_line() {
+        let options = DecodeOptions { indent: Indent::Tabs(1), bracket_on_new_line: true, ..Default::default() };
+        assert_eq!("[\n\tArr(\n\t[\n\t\tU(1),\n\t\tMap(\n\t\t{\n\t\t\tStr(\"a\"): Arr([]),\n\t\t}),\n\t]),\n]",
+                   decode_pretty(&NESTED, &options));
+    }
+
+    #[test]
+    fn max_line_width() {
+        let fits = DecodeOptions { max_line_width: Some(39), ..Default::default() };
+        assert_eq!("[Arr([U(1), Map({Str(\"a\"): Arr([])})])]", decode_with_options(&NESTED, &fits));
+        // only the map fits on its line
+        let narrow = DecodeOptions { max_line_width: Some(30), ..Default::default() };
+        assert_eq!("[
+  Arr([
+    U(1),
+    Map({Str(\"a\"): Arr([])}),
+  ]),
+]", decode_with_options(&NESTED, &narrow));
+    }
+
+    #[test]
+    fn bytes_as_text_if_utf8() {
+        // h'4869', h'ff00'
+        let bytes = [0x42, b'H', b'i', 0x42, 0xff, 0x00];
+        assert_eq!("[BStr([72, 105]), BStr([255, 0])]", decode_with_options(&bytes, &DecodeOptions::default()));
+        let options = DecodeOptions { bytes_as_text_if_utf8: true, ..Default::default() };
+        assert_eq!("[BStr(b\"Hi\"), BStr([255, 0])]", decode_with_options(&bytes, &options));
+    }
+
+    #[test]
+    fn bytes_formats() {
+        // h'fbff01', h''
+        let bytes = [0x43, 0xfb, 0xff, 0x01, 0x40];
+        let formats = [
+            (BytesFormat::DecimalArray, "[BStr([251, 255, 1]), BStr([])]"),
+            (BytesFormat::Hex, "[BStr(h'fbff01'), BStr(h'')]"),
+            (BytesFormat::Base64, "[BStr(b64'+/8B'), BStr(b64'')]"),
+            (BytesFormat::Base64Url, "[BStr(b64'-_8B'), BStr(b64'')]"),
+        ];
+        for (bytes_format, expected) in formats {
+            let options = DecodeOptions { bytes_format, ..Default::default() };
+            assert_eq!(expected, decode_with_options(&bytes, &options));
+        }
+        assert_eq!("[BStr([251, 255, 1]), BStr([])]", decode(&bytes));
+    }
+
+    #[test]
+    fn float_bytes() {
+        let options = DecodeOptions { show_float_bytes: true, ..Default::default() };
+        assert_eq!("[F64(1.0 [0x3f, 0xf0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00])]",
+                   decode_with_options(&[0xfb, 0x3f, 0xf0, 0, 0, 0, 0, 0, 0], &options));
+        assert_eq!("[F32(-2.5 [0xc0, 0x20, 0x00, 0x00]), F16(1.0009766 [0x3c, 0x01])]",
+                   decode_with_options(&[0xfa, 0xc0, 0x20, 0, 0, 0xf9, 0x3c, 0x01], &options));
+        // half NaNs keep their sign and payload
+        assert_eq!("[F16(NaN [0xfe, 0x00]), F16(NaN [0x7c, 0x01]), F16(NaN [0x7e, 0x00])]",
+                   decode_with_options(&[0xf9, 0xfe, 0x00, 0xf9, 0x7c, 0x01, 0xf9, 0x7e, 0x00], &options));
+    }
+
+    #[test]
+    fn float_classes() {
+        let options = DecodeOptions { classify_floats: true, ..Default::default() };
+        // 1.0, 2^-24, 0.0, -Infinity and NaN as half floats
+        let half = [0xf9, 0x3c, 0x00, 0xf9, 0x00, 0x01, 0xf9, 0x00, 0x00, 0xf9, 0xfc, 0x00, 0xf9, 0x7e, 0x00];
+        assert_eq!("[F16(1.0 [Normal]), F16(5.9604645e-8 [Subnormal]), F16(0.0 [Zero]), F16(-inf [Infinite]), F16(NaN [NaN])]",
+                   decode_with_options(&half, &options));
+        // 2^-24 is normal as a single float, the smallest subnormal one is not; -0.0, Infinity and NaN
+        let single = [0xfa, 0x33, 0x80, 0x00, 0x00, 0xfa, 0x00, 0x00, 0x00, 0x01, 0xfa, 0x80, 0x00, 0x00, 0x00,
+            0xfa, 0x7f, 0x80, 0x00, 0x00, 0xfa, 0x7f, 0xc0, 0x00, 0x00];
+        assert_eq!("[F32(5.9604645e-8 [Normal]), F32(1e-45 [Subnormal]), F32(-0.0 [Zero]), F32(inf [Infinite]), F32(NaN [NaN])]",
+                   decode_with_options(&single, &options));
+        // 1.5, the smallest subnormal double, 0.0, -Infinity and NaN
+        let double = [0xfb, 0x3f, 0xf8, 0, 0, 0, 0, 0, 0, 0xfb, 0, 0, 0, 0, 0, 0, 0, 0x01, 0xfb, 0, 0, 0, 0, 0, 0, 0, 0,
+            0xfb, 0xff, 0xf0, 0, 0, 0, 0, 0, 0, 0xfb, 0x7f, 0xf8, 0, 0, 0, 0, 0, 0];
+        assert_eq!("[F64(1.5 [Normal]), F64(5e-324 [Subnormal]), F64(0.0 [Zero]), F64(-inf [Infinite]), F64(NaN [NaN])]",
+                   decode_with_options(&double, &options));
+        let both = DecodeOptions { show_float_bytes: true, ..options };
+        assert_eq!("[F16(0.0 [Zero] [0x00, 0x00])]", decode_with_options(&[0xf9, 0x00, 0x00], &both));
+    }
+
+    #[test]
+    fn tag_names() {
+        // 1(0), 1004("2020-01-01"), 9999(0)
+        let bytes = [0xc1, 0x00, 0xd9, 0x03, 0xec, 0x6a, b'2', b'0', b'2', b'0', b'-', b'0', b'1', b'-', b'0', b'1', 0xd9, 0x27, 0x0f, 0x00];
+        let options = DecodeOptions { annotate_tag_names: true, ..Default::default() };
+        assert_eq!("[Tag(1 /epoch-date-time/, U(0)), Tag(1004 /full-date-string/, Str(\"2020-01-01\")), Tag(9999, U(0))]",
+                   decode_with_options(&bytes, &options));
+        assert_eq!("[Tag(1, U(0)), Tag(1004, Str(\"2020-01-01\")), Tag(9999, U(0))]",
+                   decode_with_options(&bytes, &DecodeOptions::default()));
+    }
+
+    #[test]
+    fn array_display_capped() {
+        // [0, 1, ..., 999], [1]
+        let mut bytes = vec![0x99, 0x03, 0xe8];
+        for i in 0..1000_u16 {
+            bytes.extend([0x19, (i >> 8) as u8, i as u8]);
+        }
+        bytes.extend([0x81, 0x01]);
+        let options = DecodeOptions { max_array_display: Some(5), ..Default::default() };
+        assert_eq!("[Arr([U(0), U(1), U(2), U(3), U(4), ...(1000 elements total)]), Arr([U(1)])]",
+                   decode_with_options(&bytes, &options));
+        assert_eq!("[\n  Arr([\n    ...(1 elements total),\n  ]),\n]",
+                   decode_pretty(&[0x81, 0x01], &DecodeOptions { max_array_display: Some(0), ..Default::default() }));
+    }
+
+    #[test]
+    fn fold_repeated_elements() {
+        // [0, 0, ..., 0] with 5000 zeros, then [1, 2, 2, 1]
+        let mut bytes = vec![0x99, 0x13, 0x88];
+        bytes.extend([0; 5000]);
+        bytes.extend([0x84, 0x01, 0x02, 0x02, 0x01]);
+        let options = DecodeOptions { fold_repeated_elements: true, ..Default::default() };
+        assert_eq!("[Arr([U(0) ×5000]), Arr([U(1), U(2) ×2, U(1)])]", decode_with_options(&bytes, &options));
+        let options = DecodeOptions { max_array_display: Some(3), ..options };
+        assert_eq!("[Arr([U(0) ×3, ...(5000 elements total)]), Arr([U(1), U(2) ×2, ...(4 elements total)])]",
+                   decode_with_options(&bytes, &options));
+    }
+
+    #[test]
+    fn expected_encodings() {
+        // 23("01ff"), 22("AQ=="), 21("AQ"), 23("xyz")
+        let bytes = [0xd7, 0x64, b'0', b'1', b'f', b'f', 0xd6, 0x64, b'A', b'Q', b'=', b'=', 0xd5, 0x62, b'A', b'Q',
+            0xd7, 0x63, b'x', b'y', b'z'];
+        let options = DecodeOptions { decode_expected_encodings: true, ..Default::default() };
+        assert_eq!("[Tag(23, BStr([1, 255])), Tag(22, BStr([1])), Tag(21, BStr([1])), Tag(23, Str(\"xyz\"))]",
+                   decode_with_options(&bytes, &options));
+        assert_eq!("[Tag(23, Str(\"01ff\"))]", decode_with_options(&bytes[..6], &DecodeOptions::default()));
+    }
+
+    #[test]
+    fn hexadecimal_integers() {
+        // [0, 255, -16, 18446744073709551615, -18446744073709551616]
+        let bytes = [0x85, 0x00, 0x18, 0xff, 0x2f, 0x1b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+            0x3b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff];
+        let options = DecodeOptions { int_radix: Radix::Hex, ..Default::default() };
+        assert_eq!("[Arr([U(0x0), U(0xff), N(-0x10), U(0xffffffffffffffff), N(-0x10000000000000000)])]",
+                   decode_with_options(&bytes, &options));
+        assert_eq!("[Arr([U(0), U(255), N(-16), U(18446744073709551615), N(-18446744073709551616)])]",
+                   decode_with_options(&bytes, &DecodeOptions::default()));
+    }
+
+    #[test]
+    fn encoded_sequence() {
+        // 63(h'0102'), 63(h''), 63(h'18')
+        let bytes = [0xd8, 0x3f, 0x42, 0x01, 0x02, 0xd8, 0x3f, 0x40, 0xd8, 0x3f, 0x41, 0x18];
+        assert_eq!("[CborSeq([U(1), U(2)]), CborSeq([]), Tag(63, BStr([24]))]", decode(&bytes));
+        // nested items render as any other
+        let options = DecodeOptions { int_radix: Radix::Hex, ..Default::default() };
+        assert_eq!("[CborSeq([U(0x1), U(0x2)])]", decode_with_options(&bytes[..5], &options));
+    }
+
+    #[test]
+    fn homogeneous_arrays() {
+        // 41([1, 2]), 41("a")
+        let bytes = [0xd8, 0x29, 0x82, 0x01, 0x02, 0xd8, 0x29, 0x61, b'a'];
+        assert_eq!("[Array[homogeneous]([U(1), U(2)]), Tag(41, Str(\"a\"))]", decode(&bytes));
+        assert_eq!("[\n  Array[homogeneous]([\n    U(1),\n    U(2),\n  ]),\n  Tag(41, Str(\"a\")),\n]",
+                   decode_pretty(&bytes, &DecodeOptions::default()));
+    }
+
+    #[test]
+    fn sorted_sets() {
+        // 258([3, "a", 1]), 258([1, 3, "a"]), [3, 1]
+        let first = [0xd9, 0x01, 0x02, 0x83, 0x03, 0x61, b'a', 0x01, 0x82, 0x03, 0x01];
+        let second = [0xd9, 0x01, 0x02, 0x83, 0x01, 0x03, 0x61, b'a', 0x82, 0x03, 0x01];
+        let options = DecodeOptions { sort_sets: true, ..Default::default() };
+        assert_eq!("[Tag(258, Arr([U(1), U(3), Str(\"a\")])), Arr([U(3), U(1)])]", decode_with_options(&first, &options));
+        assert_eq!(decode_with_options(&first, &options), decode_with_options(&second, &options));
+        assert_ne!(decode_with_options(&first, &DecodeOptions::default()), decode_with_options(&second, &DecodeOptions::default()));
+    }
+
+    #[test]
+    fn cose_algorithm_names() {
+        // {1: -7, 4: -7}, {1: -99}
+        let bytes = [0xa2, 0x01, 0x26, 0x04, 0x26, 0xa1, 0x01, 0x38, 0x62];
+        let options = DecodeOptions { cose_algorithm_names: true, ..Default::default() };
+        assert_eq!("[Map({U(1): N(-7 /ES256/), U(4): N(-7)}), Map({U(1): N(-99)})]", decode_with_options(&bytes, &options));
+        assert_eq!("[Map({U(1): N(-7), U(4): N(-7)}), Map({U(1): N(-99)})]", decode_with_options(&bytes, &DecodeOptions::default()));
+    }
+}
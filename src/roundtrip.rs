@@ -0,0 +1,178 @@
+use crate::MajorType::{self, *};
+use crate::encode::write_head;
+use crate::{decode_at, encode, get_int, skip, DecodeError};
+
+/// How the input compares with its deterministic re-encoding, see [`roundtrip_report`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoundtripReport {
+    /// Whether the input is the same as `canonical`, byte for byte.
+    pub identical: bool,
+    /// The input decoded and encoded again with [`encode`].
+    pub canonical: Vec<u8>,
+    /// Where the input differs from `canonical`, ordered by offset.
+    pub differences: Vec<Difference>,
+}
+
+/// A part of the input, starting at `offset`, that is encoded as `input` where the deterministic
+/// encoding has `canonical`. For a header that is just the header, for a float the float and for
+/// key order the whole map.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Difference {
+    pub offset: usize,
+    pub kind: DifferenceKind,
+    pub input: Vec<u8>,
+    pub canonical: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DifferenceKind {
+    /// The integer, length or tag number is encoded in more bytes than needed.
+    NonMinimal,
+    /// The string, array or map has an indefinite length.
+    Indefinite,
+    /// The float is wider than it needs to be to keep its value.
+    FloatWidth,
+    /// The map entries are not sorted by the encoded bytes of their keys.
+    KeyOrder,
+}
+
+/// Decodes all top-level items, encodes them again deterministically and reports whether that
+/// gives back the input, and where it does not, why: to test another encoder against this crate.
+/// The chunks of an indefinite-length string are not compared, as they are joined into one.
+pub fn roundtrip_report(bytes: &[u8]) -> Result<RoundtripReport, DecodeError> {
+    let mut canonical = Vec::new();
+    let mut differences = Vec::new();
+    let mut idx = 0;
+    while idx < bytes.len() {
+        let start = idx;
+        let item = decode_at(bytes, &mut idx)?;
+        canonical.extend(compare(&item, bytes, &mut start.clone(), &mut differences)?);
+    }
+    Ok(RoundtripReport { identical: canonical == bytes, canonical, differences })
+}
+
+/// Walks the input of `item` at `idx` along with the item, building its deterministic encoding
+/// from those of its children, which it returns. Adds where the two differ to `differences` and
+/// advances `idx` past the item.
+fn compare(item: &MajorType, bytes: &[u8], idx: &mut usize, differences: &mut Vec<Difference>) -> Result<Vec<u8>, DecodeError> {
+    let start = *idx;
+    let major_type = bytes[start] >> 5;
+    if major_type == 7 {
+        skip(bytes, idx)?;
+        let canonical = encode(item).expect("decoded items can be encoded");
+        if bytes[start..*idx] != canonical {
+            differences.push(Difference { offset: start, kind: DifferenceKind::FloatWidth, input: bytes[start..*idx].to_vec(), canonical: canonical.clone() });
+        }
+        return Ok(canonical);
+    }
+
+    let indefinite = bytes[start] & 0b00011111 == 31;
+    let argument = if indefinite {
+        *idx += 1;
+        let len = match item {
+            BStr(content) => content.len(),
+            Str(text) => text.len(),
+            Arr(items) => items.len(),
+            Map(entries) => entries.len(),
+            _ => unreachable!("only strings, arrays and maps have indefinite lengths"),
+        };
+        len as u64
+    } else {
+        get_int(bytes, idx)?
+    };
+    let mut canonical = Vec::new();
+    write_head(major_type, argument, &mut canonical);
+    if bytes[start..*idx] != canonical {
+        let kind = if indefinite { DifferenceKind::Indefinite } else { DifferenceKind::NonMinimal };
+        differences.push(Difference { offset: start, kind, input: bytes[start..*idx].to_vec(), canonical: canonical.clone() });
+    }
+
+    match item {
+        // the chunks of a string are joined in the deterministic encoding, so there is nothing to compare them with
+        BStr(content) => {
+            *idx = start;
+            skip(bytes, idx)?;
+            canonical.extend_from_slice(content);
+        }
+        Str(text) => {
+            *idx = start;
+            skip(bytes, idx)?;
+            canonical.extend_from_slice(text.as_bytes());
+        }
+        Arr(items) => {
+            for item in items {
+                canonical.extend(compare(item, bytes, idx, differences)?);
+            }
+            if indefinite {
+                // past the break
+                *idx += 1;
+            }
+        }
+        Map(entries) => {
+            let position = differences.len();
+            let mut encoded = Vec::with_capacity(entries.len());
+            for (key, value) in entries {
+                encoded.push((compare(key, bytes, idx, differences)?, compare(value, bytes, idx, differences)?));
+            }
+            let sorted = encoded.is_sorted_by(|(a, _), (b, _)| a <= b);
+            encoded.sort_by(|(a, _), (b, _)| a.cmp(b));
+            for (key, value) in encoded {
+                canonical.extend(key);
+                canonical.extend(value);
+            }
+            if indefinite {
+                // past the break
+                *idx += 1;
+            }
+            if !sorted {
+                let difference = Difference { offset: start, kind: DifferenceKind::KeyOrder, input: bytes[start..*idx].to_vec(), canonical: canonical.clone() };
+                differences.insert(position, difference);
+            }
+        }
+        Tag(_, content) => canonical.extend(compare(content, bytes, idx, differences)?),
+        _ => {}
+    }
+    Ok(canonical)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn non_canonical_map() {
+        // {"b": 1 in two bytes, "a": [_ 1, 2], "c": 1.0 as a double}, with its length in two bytes
+        let bytes = [0xb8, 0x03, 0x61, b'b', 0x18, 0x01, 0x61, b'a', 0x9f, 0x01, 0x02, 0xff,
+            0x61, b'c', 0xfb, 0x3f, 0xf0, 0, 0, 0, 0, 0, 0];
+        let canonical = vec![0xa3, 0x61, b'a', 0x82, 0x01, 0x02, 0x61, b'b', 0x01, 0x61, b'c', 0xf9, 0x3c, 0x00];
+        let difference = |offset, kind, input: &[u8], canonical: &[u8]| Difference {
+            offset,
+            kind,
+            input: input.to_vec(),
+            canonical: canonical.to_vec(),
+        };
+        assert_eq!(Ok(RoundtripReport {
+            identical: false,
+            canonical: canonical.clone(),
+            differences: vec![
+                difference(0, DifferenceKind::NonMinimal, &[0xb8, 0x03], &[0xa3]),
+                difference(0, DifferenceKind::KeyOrder, &bytes, &canonical),
+                difference(4, DifferenceKind::NonMinimal, &[0x18, 0x01], &[0x01]),
+                difference(8, DifferenceKind::Indefinite, &[0x9f], &[0x82]),
+                difference(14, DifferenceKind::FloatWidth, &bytes[14..], &[0xf9, 0x3c, 0x00]),
+            ],
+        }), roundtrip_report(&bytes));
+    }
+
+    #[test]
+    fn canonical_sequence() {
+        // 1, {0: []}
+        let report = roundtrip_report(&[0x01, 0xa1, 0x00, 0x80]).unwrap();
+        assert!(report.identical && report.differences.is_empty());
+        // 1, (_ "a" "b"), which joins into "ab"
+        let report = roundtrip_report(&[0x01, 0x7f, 0x61, b'a', 0x61, b'b', 0xff]).unwrap();
+        assert_eq!(vec![0x01, 0x62, b'a', b'b'], report.canonical);
+        assert_eq!(vec![Difference { offset: 1, kind: DifferenceKind::Indefinite, input: vec![0x7f], canonical: vec![0x62] }],
+                   report.differences);
+    }
+}
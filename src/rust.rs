@@ -0,0 +1,100 @@
+use std::fmt::Write;
+
+use crate::MajorType::{self, *};
+use crate::decode_items;
+
+/// Renders all top-level items as the Rust expression of a `Vec<MajorType>` that holds them, such
+/// as `vec![Arr(vec![U(1), Str("a".into())])]`, to paste into a test as the expected value.
+/// The expression names the variants unqualified, as after `use cbor_debug::MajorType::*`.
+pub fn decode_to_rust(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    write_list(&decode_items(bytes), &mut out, write_item);
+    out
+}
+
+/// Writes `vec![...]` with the entries of `entries` written by `write_entry`.
+fn write_list<T>(entries: &[T], out: &mut String, write_entry: fn(&T, &mut String)) {
+    out.push_str("vec![");
+    for (i, entry) in entries.iter().enumerate() {
+        if i > 0 {
+            out.push_str(", ");
+        }
+        write_entry(entry, out);
+    }
+    out.push(']');
+}
+
+fn write_item(item: &MajorType, out: &mut String) {
+    match item {
+        BStr(bytes) => {
+            out.push_str("BStr(");
+            write_list(bytes, out, |b, out| write!(out, "{}", b).unwrap());
+            out.push(')');
+        }
+        Str(text) => write!(out, "Str({:?}.into())", text).unwrap(),
+        Arr(items) => {
+            out.push_str("Arr(");
+            write_list(items, out, write_item);
+            out.push(')');
+        }
+        Map(entries) => {
+            out.push_str("Map(");
+            write_list(entries, out, |(key, value), out| {
+                out.push('(');
+                write_item(key, out);
+                out.push_str(", ");
+                write_item(value, out);
+                out.push(')');
+            });
+            out.push(')');
+        }
+        Tag(number, content) => {
+            write!(out, "Tag({}, Box::new(", number).unwrap();
+            write_item(content, out);
+            out.push_str("))");
+        }
+        F16(v) => write!(out, "F16({})", float_literal(*v as f64, "f32")).unwrap(),
+        F32(v) => write!(out, "F32({})", float_literal(*v as f64, "f32")).unwrap(),
+        F64(v) => write!(out, "F64({})", float_literal(*v, "f64")).unwrap(),
+        // the other variants print as they are constructed
+        scalar => write!(out, "{:?}", scalar).unwrap(),
+    }
+}
+
+/// A float literal, or the constant of the `float` type for values that have none.
+fn float_literal(v: f64, float: &str) -> String {
+    if v.is_nan() {
+        format!("{}::NAN", float)
+    } else if v.is_infinite() {
+        format!("{}::{}", float, if v > 0.0 { "INFINITY" } else { "NEG_INFINITY" })
+    } else if float == "f32" {
+        format!("{:?}", v as f32)
+    } else {
+        format!("{:?}", v)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn nested_structure() {
+        // [1, -2, h'ff', {"a\n": 1(1.5)}, [simple(16), null, -Infinity]]
+        let bytes = [0x85, 0x01, 0x21, 0x41, 0xff, 0xa1, 0x62, b'a', b'\n', 0xc1, 0xf9, 0x3e, 0x00,
+            0x83, 0xf0, 0xf6, 0xfa, 0xff, 0x80, 0x00, 0x00];
+        let rust = decode_to_rust(&bytes);
+        assert_eq!("vec![Arr(vec![U(1), N(-2), BStr(vec![255]), Map(vec![(Str(\"a\\n\".into()), Tag(1, Box::new(F16(1.5))))]), \
+                    Arr(vec![Simple(16), Null, F32(f32::NEG_INFINITY)])])]", rust);
+        // the same expression, pasted
+        let expected: Vec<MajorType> = vec![Arr(vec![U(1), N(-2), BStr(vec![255]), Map(vec![(Str("a\n".into()), Tag(1, Box::new(F16(1.5))))]),
+            Arr(vec![Simple(16), Null, F32(f32::NEG_INFINITY)])])];
+        assert_eq!(expected, decode_items(&bytes));
+    }
+
+    #[test]
+    fn sequence_and_nothing() {
+        assert_eq!("vec![True, F64(0.1)]", decode_to_rust(&[0xf5, 0xfb, 0x3f, 0xb9, 0x99, 0x99, 0x99, 0x99, 0x99, 0x9a]));
+        assert_eq!("vec![]", decode_to_rust(&[]));
+    }
+}
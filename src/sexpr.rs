@@ -0,0 +1,120 @@
+use std::fmt::Write;
+
+use crate::MajorType::{self, *};
+use crate::decode_items;
+
+/// Renders each top-level item on its own line as an S-expression, for reading with Lisp or Scheme,
+/// e.g. `(array (uint 1) (text "a") (map ((uint 1) (text "x"))))`.
+///
+/// Every item is a list headed by its kind: `uint`, `nint`, `bytes`, `text`, `array`, `map`, `tag`,
+/// `float`, `simple`, or one of `false`, `true`, `null`, `undefined` and `invalid` on its own.
+/// Each map entry is a list of the key and the value. Byte strings are R7RS bytevectors, e.g.
+/// `(bytes #u8(1 2))`, and NaN and infinite floats are written `+nan.0`, `+inf.0` and `-inf.0`.
+pub fn decode_to_sexpr(bytes: &[u8]) -> String {
+    decode_items(bytes).iter()
+        .map(|item| {
+            let mut out = String::new();
+            write_item(item, &mut out);
+            out
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn write_item(item: &MajorType, out: &mut String) {
+    match item {
+        U(v) => write!(out, "(uint {})", v).unwrap(),
+        N(v) => write!(out, "(nint {})", v).unwrap(),
+        BStr(bytes) => {
+            out.push_str("(bytes #u8(");
+            for (i, byte) in bytes.iter().enumerate() {
+                if i > 0 {
+                    out.push(' ');
+                }
+                write!(out, "{}", byte).unwrap();
+            }
+            out.push_str("))");
+        }
+        Str(text) => {
+            out.push_str("(text ");
+            write_string(text, out);
+            out.push(')');
+        }
+        Arr(items) => {
+            out.push_str("(array");
+            for item in items {
+                out.push(' ');
+                write_item(item, out);
+            }
+            out.push(')');
+        }
+        Map(entries) => {
+            out.push_str("(map");
+            for (key, value) in entries {
+                out.push_str(" (");
+                write_item(key, out);
+                out.push(' ');
+                write_item(value, out);
+                out.push(')');
+            }
+            out.push(')');
+        }
+        Tag(number, content) => {
+            write!(out, "(tag {} ", number).unwrap();
+            write_item(content, out);
+            out.push(')');
+        }
+        False => out.push_str("(false)"),
+        True => out.push_str("(true)"),
+        Null => out.push_str("(null)"),
+        Undefined => out.push_str("(undefined)"),
+        Simple(v) => write!(out, "(simple {})", v).unwrap(),
+        F16(v) | F32(v) => write_float(*v as f64, out),
+        F64(v) => write_float(*v, out),
+        Invalid => out.push_str("(invalid)"),
+    }
+}
+
+fn write_float(v: f64, out: &mut String) {
+    if v.is_nan() {
+        out.push_str("(float +nan.0)");
+    } else if v.is_infinite() {
+        out.push_str(if v > 0.0 { "(float +inf.0)" } else { "(float -inf.0)" });
+    } else {
+        write!(out, "(float {:?})", v).unwrap();
+    }
+}
+
+/// Writes `text` as a string literal with the escapes R7RS defines, which other control
+/// characters are written with as hex, e.g. `\x7f;`.
+fn write_string(text: &str, out: &mut String) {
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => write!(out, "\\x{:x};", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn nested_structure() {
+        // [1, "a", {1: "x"}]
+        let bytes = [0x83, 0x01, 0x61, b'a', 0xa1, 0x01, 0x61, b'x'];
+        assert_eq!("(array (uint 1) (text \"a\") (map ((uint 1) (text \"x\"))))", decode_to_sexpr(&bytes));
+        // 1([-1, h'0102', null, "\"\x7f"]), -1.5, {}
+        let bytes = [0xc1, 0x84, 0x20, 0x42, 0x01, 0x02, 0xf6, 0x62, b'"', 0x7f, 0xf9, 0xbe, 0x00, 0xa0];
+        assert_eq!("(tag 1 (array (nint -1) (bytes #u8(1 2)) (null) (text \"\\\"\\x7f;\")))\n(float -1.5)\n(map)",
+                   decode_to_sexpr(&bytes));
+    }
+}
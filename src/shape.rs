@@ -0,0 +1,246 @@
+use std::fmt::Write;
+
+use crate::MajorType::{self, *};
+use crate::query::{Path, Segment};
+use crate::{decode_at_offset, DecodeError};
+
+/// The expected layout of a CBOR item, see [`matches_shape`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Shape {
+    Any,
+    Int,
+    Float,
+    Text,
+    Bytes,
+    Bool,
+    Null,
+    /// An array whose elements all have the given shape.
+    Array(Box<Shape>),
+    /// A map that has at least the given keys, with values of the given shapes.
+    Map(Vec<(Key, Shape)>),
+    AnyOf(Vec<Shape>),
+}
+
+/// A map key in a [`Shape`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Key {
+    Int(i128),
+    Text(String),
+}
+
+/// A place where the decoded item does not have the expected shape.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShapeError {
+    pub path: Path,
+    pub expected: String,
+    pub found: String,
+}
+
+/// Checks that the first item in `bytes` has the layout described by `shape`,
+/// returning every mismatch with its path.
+pub fn matches_shape(bytes: &[u8], shape: &Shape) -> Result<(), Vec<ShapeError>> {
+    let item = decode_at_offset(bytes, 0)
+        .map_err(|e| vec![ShapeError { path: Path::default(), expected: shape.to_string(), found: e.to_string() }])?
+        .0;
+    let mut errors = Vec::new();
+    check(&item, shape, &Path::default(), &mut errors);
+    if errors.is_empty() { Ok(()) } else { Err(errors) }
+}
+
+fn check(item: &MajorType, shape: &Shape, path: &Path, errors: &mut Vec<ShapeError>) {
+    let mismatch = |errors: &mut Vec<ShapeError>| errors.push(ShapeError {
+        path: path.clone(),
+        expected: shape.to_string(),
+        found: item.type_name().to_string(),
+    });
+    match (shape, item) {
+        (Shape::Any, _)
+        | (Shape::Int, U(_) | N(_))
+        | (Shape::Float, F16(_) | F32(_) | F64(_))
+        | (Shape::Text, Str(_))
+        | (Shape::Bytes, BStr(_))
+        | (Shape::Bool, True | False)
+        | (Shape::Null, Null) => {}
+        (Shape::Array(element), Arr(items)) => {
+            for (i, item) in items.iter().enumerate() {
+                check(item, element, &path.child(Segment::Index(i)), errors);
+            }
+        }
+        (Shape::Map(fields), Map(entries)) => {
+            for (key, value_shape) in fields {
+                let key = key.item();
+                let field_path = path.child(Segment::Key(key.clone()));
+                match entries.iter().find(|(k, _)| *k == key) {
+                    Some((_, value)) => check(value, value_shape, &field_path, errors),
+                    None => errors.push(ShapeError {
+                        path: field_path,
+                        expected: value_shape.to_string(),
+                        found: "nothing".to_string(),
+                    }),
+                }
+            }
+        }
+        (Shape::AnyOf(alternatives), _) => {
+            let matches = |alternative| {
+                let mut errors = Vec::new();
+                check(item, alternative, path, &mut errors);
+                errors.is_empty()
+            };
+            if !alternatives.iter().any(matches) {
+                mismatch(errors);
+            }
+        }
+        _ => mismatch(errors),
+    }
+}
+
+/// Renders the first item in `bytes` with every value that `shape` has a type for followed by that
+/// type in CDDL notation, e.g. `Str("x") /: tstr/`, whether or not the value has it. Arrays and maps
+/// that are expected show the types of their contents instead of their own.
+pub fn decode_with_shape(bytes: &[u8], shape: &Shape) -> Result<String, DecodeError> {
+    let (item, _) = decode_at_offset(bytes, 0)?;
+    let mut out = String::new();
+    annotate(&item, Some(shape), &mut out);
+    Ok(out)
+}
+
+fn annotate(item: &MajorType, shape: Option<&Shape>, out: &mut String) {
+    match (item, shape) {
+        (Arr(items), Some(Shape::Array(element))) => {
+            out.push_str("Arr([");
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                annotate(item, Some(element), out);
+            }
+            out.push_str("])");
+        }
+        (Map(entries), Some(Shape::Map(fields))) => {
+            out.push_str("Map({");
+            for (i, (key, value)) in entries.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                let field = fields.iter().find(|(field, _)| field.item() == *key);
+                write!(out, "{:?}: ", key).unwrap();
+                annotate(value, field.map(|(_, shape)| shape), out);
+            }
+            out.push_str("})");
+        }
+        (item, Some(shape)) => write!(out, "{:?} /: {}/", item, shape.cddl()).unwrap(),
+        (item, None) => write!(out, "{:?}", item).unwrap(),
+    }
+}
+
+impl Key {
+    fn item(&self) -> MajorType {
+        match self {
+            Key::Int(v) if *v >= 0 => U(*v as u64),
+            Key::Int(v) => N(*v),
+            Key::Text(text) => Str(text.as_str().into()),
+        }
+    }
+}
+
+impl Shape {
+    /// The CDDL type this shape corresponds to, e.g. `[* int / tstr]`.
+    fn cddl(&self) -> String {
+        match self {
+            Shape::Any => "any".to_string(),
+            Shape::Int => "int".to_string(),
+            Shape::Float => "float".to_string(),
+            Shape::Text => "tstr".to_string(),
+            Shape::Bytes => "bstr".to_string(),
+            Shape::Bool => "bool".to_string(),
+            Shape::Null => "nil".to_string(),
+            Shape::Array(element) => format!("[* {}]", element.cddl()),
+            Shape::Map(fields) => {
+                let fields = fields.iter()
+                    .map(|(key, shape)| match key {
+                        Key::Int(v) => format!("{} => {}", v, shape.cddl()),
+                        Key::Text(text) => format!("{:?} => {}", text, shape.cddl()),
+                    })
+                    .collect::<Vec<_>>();
+                format!("{{ {} }}", fields.join(", "))
+            }
+            Shape::AnyOf(alternatives) => alternatives.iter().map(Shape::cddl).collect::<Vec<_>>().join(" / "),
+        }
+    }
+}
+
+impl std::fmt::Display for Shape {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Shape::Any => f.write_str("any"),
+            Shape::Int => f.write_str("int"),
+            Shape::Float => f.write_str("float"),
+            Shape::Text => f.write_str("text"),
+            Shape::Bytes => f.write_str("bytes"),
+            Shape::Bool => f.write_str("bool"),
+            Shape::Null => f.write_str("null"),
+            Shape::Array(element) => write!(f, "array of {}", element),
+            Shape::Map(_) => f.write_str("map"),
+            Shape::AnyOf(alternatives) => {
+                let names = alternatives.iter().map(|shape| shape.to_string()).collect::<Vec<_>>();
+                write!(f, "any of {}", names.join(", "))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn message_shape() -> Shape {
+        Shape::Map(vec![
+            (Key::Text("id".into()), Shape::Int),
+            (Key::Int(1), Shape::Array(Box::new(Shape::AnyOf(vec![Shape::Int, Shape::Text])))),
+        ])
+    }
+
+    #[test]
+    fn matching_message() {
+        // {"id": 7, 1: [1, "two"], "extra": null}
+        let bytes = [0xa3, 0x62, b'i', b'd', 0x07, 0x01, 0x82, 0x01, 0x63, b't', b'w', b'o', 0x65, b'e', b'x', b't', b'r', b'a', 0xf6];
+        assert_eq!(Ok(()), matches_shape(&bytes, &message_shape()));
+    }
+
+    #[test]
+    fn non_matching_message() {
+        // {"id": "7", 1: [1, h'02']}
+        let bytes = [0xa2, 0x62, b'i', b'd', 0x61, b'7', 0x01, 0x82, 0x01, 0x41, 0x02];
+        let errors = matches_shape(&bytes, &message_shape()).unwrap_err();
+        assert_eq!(vec!["id: expected int, found text string", "[1][1]: expected any of int, text, found byte string"],
+                   errors.iter().map(|e| format!("{}: expected {}, found {}", e.path, e.expected, e.found)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn annotated_with_cddl_types() {
+        // {"id": 7, 1: [1, "two"], "extra": null}
+        let bytes = [0xa3, 0x62, b'i', b'd', 0x07, 0x01, 0x82, 0x01, 0x63, b't', b'w', b'o', 0x65, b'e', b'x', b't', b'r', b'a', 0xf6];
+        assert_eq!(Ok("Map({Str(\"id\"): U(7) /: int/, U(1): Arr([U(1) /: int / tstr/, Str(\"two\") /: int / tstr/]), \
+                       Str(\"extra\"): Null})".to_string()), decode_with_shape(&bytes, &message_shape()));
+        // {"id": [], 1: "x"}, where the array gets the type of a scalar and the text of an array
+        let bytes = [0xa2, 0x62, b'i', b'd', 0x80, 0x01, 0x61, b'x'];
+        assert_eq!(Ok("Map({Str(\"id\"): Arr([]) /: int/, U(1): Str(\"x\") /: [* int / tstr]/})".to_string()),
+                   decode_with_shape(&bytes, &message_shape()));
+        let nested = Shape::Array(Box::new(Shape::Map(vec![(Key::Int(-1), Shape::Any)])));
+        assert_eq!(Ok("U(1) /: [* { -1 => any }]/".to_string()), decode_with_shape(&[0x01], &nested));
+    }
+
+    #[test]
+    fn missing_key() {
+        let errors = matches_shape(&[0xa0], &message_shape()).unwrap_err();
+        assert_eq!((Path(vec![Segment::Key(Str("id".into()))]), "nothing".to_string()), (errors[0].path.clone(), errors[0].found.clone()));
+        assert_eq!(2, errors.len());
+    }
+
+    #[test]
+    fn malformed_input() {
+        let errors = matches_shape(&[0x82, 0x01], &Shape::Any).unwrap_err();
+        assert_eq!(vec!["input ends before the item at offset 2 is complete".to_string()],
+                   errors.into_iter().map(|error| error.found).collect::<Vec<_>>());
+    }
+}
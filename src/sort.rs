@@ -0,0 +1,107 @@
+use crate::{at_break, get_int, skip, DecodeError};
+
+/// Copies `bytes` with the entries of every map sorted by the bytes of their keys, as deterministic
+/// encoding sorts them, but with everything else as it was: integers, lengths and floats keep
+/// their width and indefinite-length items stay indefinite. The input can be a sequence of items.
+pub fn sort_map_keys(bytes: &[u8]) -> Result<Vec<u8>, DecodeError> {
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut idx = 0;
+    while idx < bytes.len() {
+        copy_sorted(bytes, &mut idx, &mut out)?;
+    }
+    Ok(out)
+}
+
+/// Copies the item at `idx` to `out` with the maps in it sorted, and advances `idx` past it.
+fn copy_sorted(bytes: &[u8], idx: &mut usize, out: &mut Vec<u8>) -> Result<(), DecodeError> {
+    let start = *idx;
+    let initial = *bytes.get(start).ok_or(DecodeError::Truncated { offset: start })?;
+    let major_type = initial >> 5;
+    let indefinite = initial & 0b00011111 == 31;
+    if !matches!(major_type, 4..=6) || (major_type == 6 && indefinite) {
+        // nothing else can hold a map, and skip rejects a tag with an indefinite length
+        skip(bytes, idx)?;
+        out.extend_from_slice(&bytes[start..*idx]);
+        return Ok(());
+    }
+    let len = if indefinite {
+        *idx += 1;
+        None
+    } else {
+        Some(get_int(bytes, idx)?)
+    };
+    out.extend_from_slice(&bytes[start..*idx]);
+    match major_type {
+        4 => match len {
+            Some(len) => {
+                for _ in 0..len {
+                    copy_sorted(bytes, idx, out)?;
+                }
+            }
+            None => {
+                while !at_break(bytes, idx)? {
+                    copy_sorted(bytes, idx, out)?;
+                }
+                out.push(0xff);
+            }
+        },
+        5 => {
+            let mut entries = Vec::new();
+            let mut read_entry = |idx: &mut usize| -> Result<(), DecodeError> {
+                let (mut key, mut value) = (Vec::new(), Vec::new());
+                copy_sorted(bytes, idx, &mut key)?;
+                copy_sorted(bytes, idx, &mut value)?;
+                entries.push((key, value));
+                Ok(())
+            };
+            match len {
+                Some(len) => {
+                    for _ in 0..len {
+                        read_entry(idx)?;
+                    }
+                }
+                None => {
+                    while !at_break(bytes, idx)? {
+                        read_entry(idx)?;
+                    }
+                }
+            }
+            // a stable sort, so that duplicate keys keep their order
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            for (key, value) in entries {
+                out.extend(key);
+                out.extend(value);
+            }
+            if len.is_none() {
+                out.push(0xff);
+            }
+        }
+        _ => copy_sorted(bytes, idx, out)?,
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn only_key_order_changes() {
+        // {"b": 1 in two bytes, "a": [_ {2: 3.0 as a double, 1: h''}]}
+        let bytes = [0xa2, 0x61, b'b', 0x19, 0x00, 0x01, 0x61, b'a', 0x9f, 0xa2, 0x02, 0xfb, 0x40, 0x08, 0, 0, 0, 0, 0, 0, 0x01, 0x40, 0xff];
+        let expected = [0xa2, 0x61, b'a', 0x9f, 0xa2, 0x01, 0x40, 0x02, 0xfb, 0x40, 0x08, 0, 0, 0, 0, 0, 0, 0xff, 0x61, b'b', 0x19, 0x00, 0x01];
+        assert_eq!(Ok(expected.to_vec()), sort_map_keys(&bytes));
+        // a map length in two bytes, and a key with a non-minimal length sorting after the shorter header
+        let bytes = [0xb9, 0x00, 0x02, 0x78, 0x01, b'a', 0x00, 0x61, b'b', 0x00];
+        let expected = [0xb9, 0x00, 0x02, 0x61, b'b', 0x00, 0x78, 0x01, b'a', 0x00];
+        assert_eq!(Ok(expected.to_vec()), sort_map_keys(&bytes));
+    }
+
+    #[test]
+    fn sorted_input_unchanged() {
+        // 1(2), {_ 1: 2, 3: 4}
+        let bytes = [0xc1, 0x02, 0xbf, 0x01, 0x02, 0x03, 0x04, 0xff];
+        assert_eq!(Ok(bytes.to_vec()), sort_map_keys(&bytes));
+        assert_eq!(Err(DecodeError::Truncated { offset: 4 }), sort_map_keys(&bytes[..4]));
+    }
+}
@@ -0,0 +1,107 @@
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::framer::Framer;
+use crate::{decode_at_offset, offset_by, DecodeError, MajorType};
+
+/// Decodes a CBOR sequence from `reader`, handing out each top-level item as soon as all of
+/// its bytes have arrived. Bytes of an incomplete item are buffered until the rest is read.
+pub fn decode_stream<R: AsyncRead + Unpin>(reader: R) -> ItemStream<R> {
+    ItemStream { reader, buffer: Vec::new(), consumed: 0, framer: Framer::default(), failed: false }
+}
+
+/// The top-level items of an asynchronously read CBOR sequence, see [`decode_stream`].
+pub struct ItemStream<R> {
+    reader: R,
+    buffer: Vec<u8>,
+    /// Number of bytes taken out of the buffer so far, to report offsets relative to the stream.
+    consumed: usize,
+    /// How far the item at the start of the buffer has been framed.
+    framer: Framer,
+    /// Whether an item could not be framed, after which nothing more is read.
+    failed: bool,
+}
+
+impl<R: AsyncRead + Unpin> ItemStream<R> {
+    /// Returns the next item, or `None` once the reader is exhausted between items.
+    /// A reader that ends in the middle of an item yields a `Truncated` error. An item whose end
+    /// cannot be found, such as one starting with a break, yields its error and ends the stream,
+    /// as the items after it cannot be told apart.
+    pub async fn next(&mut self) -> Option<Result<MajorType, DecodeError>> {
+        if self.failed {
+            return None;
+        }
+        loop {
+            if !self.buffer.is_empty() {
+                match self.framer.frame(&self.buffer) {
+                    Ok(len) => {
+                        let item = decode_at_offset(&self.buffer, 0).map(|(item, _)| item);
+                        self.buffer.drain(..len);
+                        self.consumed += len;
+                        self.framer = Framer::default();
+                        return Some(item.map_err(|e| offset_by(e, self.consumed - len)));
+                    }
+                    Err(DecodeError::Truncated { .. }) => {}
+                    Err(e) => {
+                        self.failed = true;
+                        self.buffer.clear();
+                        return Some(Err(offset_by(e, self.consumed)));
+                    }
+                }
+            }
+            let mut chunk = [0_u8; 4096];
+            match self.reader.read(&mut chunk).await {
+                Ok(0) if self.buffer.is_empty() => return None,
+                Ok(0) => {
+                    let offset = self.consumed + self.buffer.len();
+                    self.buffer.clear();
+                    return Some(Err(DecodeError::Truncated { offset }));
+                }
+                Ok(n) => self.buffer.extend_from_slice(&chunk[..n]),
+                Err(e) => return Some(Err(DecodeError::Io(e))),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use tokio::io::AsyncWriteExt;
+
+    use super::*;
+    use crate::MajorType::*;
+
+    #[tokio::test]
+    async fn items_split_over_writes() {
+        let (mut writer, reader) = tokio::io::duplex(64);
+        let mut items = decode_stream(reader);
+        tokio::spawn(async move {
+            // "Hello" arrives in two parts, the second write also carries [1, 2]
+            writer.write_all(&[0x65, b'H', b'e']).await.unwrap();
+            writer.flush().await.unwrap();
+            tokio::task::yield_now().await;
+            writer.write_all(&[b'l', b'l', b'o', 0x82, 0x01, 0x02]).await.unwrap();
+        });
+        assert_eq!(Some(Ok(Str("Hello".into()))), items.next().await);
+        assert_eq!(Some(Ok(Arr(vec![U(1), U(2)]))), items.next().await);
+        assert_eq!(None, items.next().await);
+    }
+
+    #[tokio::test]
+    async fn truncated_at_end() {
+        let (mut writer, reader) = tokio::io::duplex(64);
+        writer.write_all(&[0x01, 0x82, 0x01]).await.unwrap();
+        drop(writer);
+        let mut items = decode_stream(reader);
+        assert_eq!(Some(Ok(U(1))), items.next().await);
+        assert_eq!(Some(Err(DecodeError::Truncated { offset: 3 })), items.next().await);
+    }
+
+    #[tokio::test]
+    async fn ends_after_unframed_item() {
+        let mut items = decode_stream(&[0x01, 0xff, 0x01][..]);
+        assert_eq!(Some(Ok(U(1))), items.next().await);
+        assert_eq!(Some(Err(DecodeError::UnexpectedBreak { offset: 1 })), items.next().await);
+        assert_eq!(None, items.next().await);
+        assert_eq!(None, items.next().await);
+    }
+}
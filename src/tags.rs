@@ -0,0 +1,189 @@
+use std::collections::HashMap;
+
+use crate::MajorType::{self, *};
+use crate::{dag, decimal, time};
+
+/// Renders the content of a tag, or returns `None` to fall back to the generic `Tag(n, content)` form.
+pub type TagHandler = fn(&MajorType) -> Option<String>;
+
+/// Checks the content of a tag, returning why it is not valid content for the tag if it is not.
+pub type TagConstraint = fn(&MajorType) -> Result<(), String>;
+
+/// Renders a byte string that holds some other encoding, or returns `None` to fall back to the
+/// generic `BStr([...])` form.
+pub type BytesRenderer = fn(&[u8]) -> Option<String>;
+
+/// Tag numbers with a custom rendering. Tags that are not registered render as `Tag(n, content)`.
+/// Tags can also carry a constraint on their content, which
+/// [`decode_with_tag_constraints`](crate::decode_with_tag_constraints) enforces.
+#[derive(Clone, Default)]
+pub struct TagRegistry {
+    handlers: HashMap<u64, TagHandler>,
+    constraints: HashMap<u64, TagConstraint>,
+    /// Keyed by the tag the byte strings must be directly under, or `None` for all byte strings.
+    bytes_renderers: HashMap<Option<u64>, BytesRenderer>,
+}
+
+impl TagRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `handler` for tag `number`, replacing any handler registered earlier.
+    pub fn register_tag(&mut self, number: u64, handler: TagHandler) {
+        self.handlers.insert(number, handler);
+    }
+
+    /// Registers `constraint` for tag `number`, replacing any constraint registered earlier.
+    pub fn register_constraint(&mut self, number: u64, constraint: TagConstraint) {
+        self.constraints.insert(number, constraint);
+    }
+
+    /// Registers `renderer` for the byte strings directly under tag `number`, or for all byte strings
+    /// if `number` is `None`, replacing any renderer registered for them earlier. Where both apply,
+    /// the one for the tag is tried first.
+    pub fn register_bytes_renderer(&mut self, number: Option<u64>, renderer: BytesRenderer) {
+        self.bytes_renderers.insert(number, renderer);
+    }
+
+    pub(crate) fn render_bytes(&self, number: Option<u64>, bytes: &[u8]) -> Option<String> {
+        self.bytes_renderers.get(&number).and_then(|renderer| renderer(bytes))
+    }
+
+    pub(crate) fn check(&self, number: u64, content: &MajorType) -> Result<(), String> {
+        self.constraints.get(&number).map_or(Ok(()), |constraint| constraint(content))
+    }
+
+    pub(crate) fn render(&self, number: u64, content: &MajorType) -> Option<String> {
+        self.handlers.get(&number).and_then(|handler| handler(content))
+    }
+}
+
+/// Renders the tags that are shown specially without being registered, or returns `None` to fall
+/// back to the generic form: URIs and IRIs as `Uri("...")`, `Iri("...")` and `IriRef("...")`,
+/// CIDs as `Cid("...")` and the extended time tags.
+pub(crate) fn render_builtin(number: u64, content: &MajorType) -> Option<String> {
+    match (number, content) {
+        (32, Str(text)) => Some(format!("Uri({:?})", text)),
+        (266, Str(text)) => Some(format!("Iri({:?})", text)),
+        (267, Str(text)) => Some(format!("IriRef({:?})", text)),
+        (42, BStr(bytes)) => dag::render_cid(bytes).map(|cid| format!("Cid({:?})", cid)),
+        (4 | 5, _) => decimal::render(number, content),
+        _ => time::render(number, content),
+    }
+}
+
+/// A constraint for tag 41, homogeneous array: the content must be an array whose elements all have
+/// the same major type. Register it to have the hint checked by
+/// [`decode_with_tag_constraints`](crate::decode_with_tag_constraints).
+pub fn homogeneous_array(content: &MajorType) -> Result<(), String> {
+    let Arr(items) = content else { return Err(format!("content of type {} is not an array", content.type_name())) };
+    let Some(first) = items.first() else { return Ok(()) };
+    match items.iter().position(|item| item.type_name() != first.type_name()) {
+        Some(i) => Err(format!("element {} has type {}, the first has type {}", i, items[i].type_name(), first.type_name())),
+        None => Ok(()),
+    }
+}
+
+/// The name of a commonly used tag from the IANA "CBOR Tags" registry, if `number` is one of them.
+pub fn tag_name(number: u64) -> Option<&'static str> {
+    Some(match number {
+        0 => "standard-date-time-string",
+        1 => "epoch-date-time",
+        2 => "unsigned-bignum",
+        3 => "negative-bignum",
+        4 => "decimal-fraction",
+        5 => "bigfloat",
+        16 => "cose-encrypt0",
+        17 => "cose-mac0",
+        18 => "cose-sign1",
+        21 => "expected-base64url",
+        22 => "expected-base64",
+        23 => "expected-base16",
+        24 => "encoded-cbor",
+        32 => "uri",
+        33 => "base64url",
+        34 => "base64",
+        35 => "regexp",
+        36 => "mime-message",
+        37 => "uuid",
+        41 => "homogeneous-array",
+        42 => "ipld-cid",
+        52 => "ipv4",
+        54 => "ipv6",
+        61 => "cwt",
+        63 => "encoded-cbor-sequence",
+        96 => "cose-encrypt",
+        97 => "cose-mac",
+        98 => "cose-sign",
+        100 => "days-since-epoch",
+        258 => "set",
+        266 => "iri",
+        267 => "iri-reference",
+        1001 => "extended-time",
+        1002 => "duration",
+        1003 => "period",
+        1004 => "full-date-string",
+        55799 => "self-described-cbor",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{decode, decode_with_tag_constraints, decode_with_tags, DecodeError};
+
+    #[test]
+    fn bytes_renderers() {
+        let mut tags = TagRegistry::new();
+        // every byte string that is ASCII, and the length of those under tag 99
+        tags.register_bytes_renderer(None, |bytes| bytes.is_ascii().then(|| format!("Ascii({:?})", String::from_utf8_lossy(bytes))));
+        tags.register_bytes_renderer(Some(99), |bytes| Some(format!("Opaque({} bytes)", bytes.len())));
+        // [h'6869', h'ff', 99(h'6869'), 98(h'6869')]
+        let bytes = [0x84, 0x42, b'h', b'i', 0x41, 0xff, 0xd8, 0x63, 0x42, b'h', b'i', 0xd8, 0x62, 0x42, b'h', b'i'];
+        assert_eq!("[Arr([Ascii(\"hi\"), BStr([255]), Tag(99, Opaque(2 bytes)), Tag(98, Ascii(\"hi\"))])]",
+                   decode_with_tags(&bytes, &tags));
+    }
+
+    #[test]
+    fn content_constraint() {
+        let mut tags = TagRegistry::new();
+        tags.register_constraint(37, |content| match content {
+            BStr(bytes) if bytes.len() == 16 => Ok(()),
+            _ => Err("a UUID is 16 bytes".to_string()),
+        });
+        // [37(h'00' * 16), 37(h'0102')]
+        let mut bytes = vec![0x82, 0xd8, 0x25, 0x50];
+        bytes.extend([0; 16]);
+        bytes.extend([0xd8, 0x25, 0x42, 0x01, 0x02]);
+        assert_eq!(Err(DecodeError::TagContentInvalid { offset: 20, tag: 37, reason: "a UUID is 16 bytes".to_string() }),
+                   decode_with_tag_constraints(&bytes, &tags));
+        assert_eq!(Ok(vec![Tag(37, Box::new(BStr(vec![0; 16])))]), decode_with_tag_constraints(&bytes[1..20], &tags));
+        assert_eq!("unexpected content for tag 37 at offset 20: a UUID is 16 bytes",
+                   decode_with_tag_constraints(&bytes, &tags).unwrap_err().to_string());
+    }
+
+    #[test]
+    fn homogeneous_array_constraint() {
+        let mut tags = TagRegistry::new();
+        tags.register_constraint(41, homogeneous_array);
+        // 41([1, 2]), 41([]), 41([1, "a"])
+        let bytes = [0xd8, 0x29, 0x82, 0x01, 0x02, 0xd8, 0x29, 0x80, 0xd8, 0x29, 0x82, 0x01, 0x61, b'a'];
+        assert!(decode_with_tag_constraints(&bytes[..8], &tags).is_ok());
+        let reason = "element 1 has type text string, the first has type unsigned integer".to_string();
+        assert_eq!(Err(DecodeError::TagContentInvalid { offset: 8, tag: 41, reason }), decode_with_tag_constraints(&bytes, &tags));
+        assert_eq!(Err("content of type map is not an array".to_string()), homogeneous_array(&Map(vec![])));
+    }
+
+    #[test]
+    fn iri_tags() {
+        // 266("https://例え.jp/パス"), 267("../ñ"), 266(1)
+        let iri = "https://例え.jp/パス";
+        let mut bytes = vec![0xd9, 0x01, 0x0a, 0x78, iri.len() as u8];
+        bytes.extend(iri.as_bytes());
+        bytes.extend([0xd9, 0x01, 0x0b, 0x65, b'.', b'.', b'/', 0xc3, 0xb1, 0xd9, 0x01, 0x0a, 0x01]);
+        assert_eq!("[Iri(\"https://例え.jp/パス\"), IriRef(\"../ñ\"), Tag(266, U(1))]", decode(&bytes));
+        assert_eq!("[Uri(\"x\")]", decode(&[0xd8, 0x20, 0x61, b'x']));
+    }
+}
@@ -0,0 +1,177 @@
+use std::fmt::Write;
+
+use crate::MajorType::{self, *};
+
+/// Renders the extended time tags of RFC 9581: 1001 (extended time), 1002 (duration) and
+/// 1003 (period). Returns `None` for other tags and for content that is not laid out as expected,
+/// so that it falls back to the generic rendering.
+pub(crate) fn render(number: u64, content: &MajorType) -> Option<String> {
+    match number {
+        1001 => Some(format!("ExtendedTime({:?})", timestamp(&Fields::of(content)?))),
+        1002 => Some(format!("Duration({:?})", duration(&Fields::of(content)?))),
+        1003 => period(content),
+        _ => None,
+    }
+}
+
+/// The numbered map entries shared by extended times and durations.
+struct Fields<'a> {
+    seconds: i128,
+    /// The fractional part as its decimal digits, e.g. `"500"` for 500 milliseconds.
+    fraction: Option<String>,
+    zone: Option<&'a str>,
+}
+
+impl<'a> Fields<'a> {
+    fn of(content: &'a MajorType) -> Option<Self> {
+        let Map(entries) = content else { return None };
+        let mut fields = Fields { seconds: 0, fraction: None, zone: None };
+        let mut has_seconds = false;
+        for (key, value) in entries {
+            match (key.as_i128()?, value) {
+                // base time in whole seconds, which is an integer rather than a bignum
+                (1, U(v)) if !has_seconds => {
+                    fields.seconds = *v as i128;
+                    has_seconds = true;
+                }
+                (1, N(v)) if !has_seconds => {
+                    fields.seconds = *v;
+                    has_seconds = true;
+                }
+                // base time in seconds as a float
+                (-1, F16(v) | F32(v)) if !has_seconds => {
+                    fields.split_float(*v as f64)?;
+                    has_seconds = true;
+                }
+                (-1, F64(v)) if !has_seconds => {
+                    fields.split_float(*v)?;
+                    has_seconds = true;
+                }
+                // milli-, micro- and nanoseconds
+                (key @ (-3 | -6 | -9), U(v)) if fields.fraction.is_none() => {
+                    let digits = -key as usize;
+                    if *v >= 10u64.pow(digits as u32) {
+                        return None;
+                    }
+                    fields.fraction = Some(format!("{:0digits$}", v));
+                }
+                // time zone hint
+                (-10, Str(zone)) if fields.zone.is_none() => fields.zone = Some(zone),
+                _ => return None,
+            }
+        }
+        has_seconds.then_some(fields)
+    }
+
+    fn split_float(&mut self, seconds: f64) -> Option<()> {
+        // bounded like integer seconds, so that the arithmetic on them cannot overflow
+        if !seconds.is_finite() || seconds.abs() >= 2f64.powi(64) || self.fraction.is_some() {
+            return None;
+        }
+        let whole = seconds.floor();
+        let nanos = ((seconds - whole) * 1e9).round() as u64;
+        // rounding can carry into the whole seconds
+        let (whole, nanos) = if nanos == 1_000_000_000 { (whole + 1.0, 0) } else { (whole, nanos) };
+        self.seconds = whole as i128;
+        if nanos > 0 {
+            self.fraction = Some(format!("{:09}", nanos).trim_end_matches('0').to_string());
+        }
+        Some(())
+    }
+}
+
+/// Formats as `2020-01-01T00:00:00.5Z`, followed by the time zone hint in brackets if there is one.
+fn timestamp(fields: &Fields) -> String {
+    let days = fields.seconds.div_euclid(86400);
+    let time = fields.seconds.rem_euclid(86400);
+    let (year, month, day) = civil_from_days(days);
+    let mut out = format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}", year, month, day, time / 3600, time / 60 % 60, time % 60);
+    if let Some(fraction) = &fields.fraction {
+        write!(out, ".{}", fraction).unwrap();
+    }
+    out.push('Z');
+    if let Some(zone) = fields.zone {
+        write!(out, "[{}]", zone).unwrap();
+    }
+    out
+}
+
+/// Formats as `90.5s`. The fraction always counts up from the seconds, so -2 seconds and
+/// 500 milliseconds make `-1.5s`.
+fn duration(fields: &Fields) -> String {
+    match &fields.fraction {
+        None => format!("{}s", fields.seconds),
+        Some(fraction) if fields.seconds >= 0 => format!("{}.{}s", fields.seconds, fraction),
+        Some(fraction) => {
+            let scale = 10i128.pow(fraction.len() as u32);
+            let part: i128 = fraction.parse().unwrap();
+            let total = fields.seconds * scale + part;
+            let sign = if total < 0 { "-" } else { "" };
+            let width = fraction.len();
+            format!("{}{}.{:0width$}s", sign, total.abs() / scale, total.abs() % scale)
+        }
+    }
+}
+
+/// A period is an array of start, end and duration, any of which may be null.
+fn period(content: &MajorType) -> Option<String> {
+    let Arr(parts) = content else { return None };
+    let [start, end, length] = parts.as_slice() else { return None };
+    let part = |item: &MajorType, render: fn(&Fields) -> String| match item {
+        Null => Some("Null".to_string()),
+        item => Some(format!("{:?}", render(&Fields::of(item)?))),
+    };
+    Some(format!("Period({}, {}, {})", part(start, timestamp)?, part(end, timestamp)?, part(length, duration)?))
+}
+
+/// The proleptic Gregorian date `days` after 1970-01-01, after Howard Hinnant's `civil_from_days`.
+fn civil_from_days(days: i128) -> (i128, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::decode;
+
+    #[test]
+    fn extended_time_with_fraction() {
+        // 1001({1: 1600000000, -3: 250})
+        let bytes = [0xd9, 0x03, 0xe9, 0xa2, 0x01, 0x1a, 0x5f, 0x5e, 0x10, 0x00, 0x22, 0x18, 0xfa];
+        assert_eq!("[ExtendedTime(\"2020-09-13T12:26:40.250Z\")]", decode(&bytes));
+    }
+
+    #[test]
+    fn duration_and_period() {
+        // 1002({1: -2, -3: 500})
+        assert_eq!("[Duration(\"-1.500s\")]", decode(&[0xd9, 0x03, 0xea, 0xa2, 0x01, 0x21, 0x22, 0x19, 0x01, 0xf4]));
+        // 1003([{1: 0}, null, {-1: 1.5}])
+        let bytes = [0xd9, 0x03, 0xeb, 0x83, 0xa1, 0x01, 0x00, 0xf6, 0xa1, 0x20, 0xf9, 0x3e, 0x00];
+        assert_eq!("[Period(\"1970-01-01T00:00:00Z\", Null, \"1.5s\")]", decode(&bytes));
+    }
+
+    #[test]
+    fn unexpected_content_falls_back() {
+        // 1001({1: 0, 99: 0}), 1001("now")
+        let bytes = [0xd9, 0x03, 0xe9, 0xa2, 0x01, 0x00, 0x18, 0x63, 0x00, 0xd9, 0x03, 0xe9, 0x63, b'n', b'o', b'w'];
+        assert_eq!("[Tag(1001, Map({U(1): U(0), U(99): U(0)})), Tag(1001, Str(\"now\"))]", decode(&bytes));
+        // 1002({1: 3(h'7fff...ff'), -3: 1}), whose seconds are a bignum
+        let mut bytes = vec![0xd9, 0x03, 0xea, 0xa2, 0x01, 0xc3, 0x50, 0x7f];
+        bytes.extend([0xff; 15]);
+        bytes.extend([0x22, 0x01]);
+        assert!(decode(&bytes).starts_with("[Tag(1002, Map({U(1): Tag(3, BStr("));
+        // 1002({-1: -1e300, -3: 1})
+        let mut bytes = vec![0xd9, 0x03, 0xea, 0xa2, 0x20, 0xfb];
+        bytes.extend((-1e300_f64).to_be_bytes());
+        bytes.extend([0x22, 0x01]);
+        assert!(decode(&bytes).starts_with("[Tag(1002, Map({N(-1): F64("));
+    }
+}
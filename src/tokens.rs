@@ -0,0 +1,84 @@
+use crate::MajorType::{self, *};
+use crate::decode_items;
+
+/// One step of the flat, document-order form of a decoded item, see [`decode_tokens`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    Uint(u64),
+    Nint(i128),
+    Bytes(Vec<u8>),
+    Text(String),
+    /// Starts an array of this many items, which is closed by an `ArrayEnd`.
+    ArrayStart(usize),
+    ArrayEnd,
+    /// Starts a map of this many pairs, which is closed by a `MapEnd`. Keys and values alternate.
+    MapStart(usize),
+    MapEnd,
+    /// Applies to the item that follows it.
+    Tag(u64),
+    Bool(bool),
+    Null,
+    Undefined,
+    Simple(u8),
+    Float(f64),
+    Invalid,
+}
+
+/// Decodes `bytes` and lists their items as tokens in document order, with the start and end of
+/// every container marked: `[1, "a"]` becomes `ArrayStart(2), Uint(1), Text("a"), ArrayEnd`.
+/// Indefinite-length containers get the length they turned out to have.
+pub fn decode_tokens(bytes: &[u8]) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    for item in decode_items(bytes) {
+        push_tokens(&item, &mut tokens);
+    }
+    tokens
+}
+
+fn push_tokens(item: &MajorType, tokens: &mut Vec<Token>) {
+    match item {
+        U(v) => tokens.push(Token::Uint(*v)),
+        N(v) => tokens.push(Token::Nint(*v)),
+        BStr(bytes) => tokens.push(Token::Bytes(bytes.clone())),
+        Str(text) => tokens.push(Token::Text(text.to_string())),
+        Arr(items) => {
+            tokens.push(Token::ArrayStart(items.len()));
+            items.iter().for_each(|item| push_tokens(item, tokens));
+            tokens.push(Token::ArrayEnd);
+        }
+        Map(entries) => {
+            tokens.push(Token::MapStart(entries.len()));
+            for (key, value) in entries {
+                push_tokens(key, tokens);
+                push_tokens(value, tokens);
+            }
+            tokens.push(Token::MapEnd);
+        }
+        Tag(number, content) => {
+            tokens.push(Token::Tag(*number));
+            push_tokens(content, tokens);
+        }
+        False => tokens.push(Token::Bool(false)),
+        True => tokens.push(Token::Bool(true)),
+        Null => tokens.push(Token::Null),
+        Undefined => tokens.push(Token::Undefined),
+        Simple(v) => tokens.push(Token::Simple(*v)),
+        F16(v) | F32(v) => tokens.push(Token::Float(*v as f64)),
+        F64(v) => tokens.push(Token::Float(*v)),
+        Invalid => tokens.push(Token::Invalid),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn nested_structure() {
+        // [1, {"a": [_ -2, 1(h'ff')]}, 1.5], null
+        let bytes = [0x83, 0x01, 0xa1, 0x61, b'a', 0x9f, 0x21, 0xc1, 0x41, 0xff, 0xff, 0xf9, 0x3e, 0x00, 0xf6];
+        assert_eq!(vec![Token::ArrayStart(3), Token::Uint(1), Token::MapStart(1), Token::Text("a".to_string()),
+                        Token::ArrayStart(2), Token::Nint(-2), Token::Tag(1), Token::Bytes(vec![0xff]), Token::ArrayEnd,
+                        Token::MapEnd, Token::Float(1.5), Token::ArrayEnd, Token::Null], decode_tokens(&bytes));
+    }
+}
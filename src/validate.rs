@@ -0,0 +1,197 @@
+use crate::{at_break, decode_at, get_int, take, DecodeError};
+
+/// A tag whose content has a major type the tag's definition does not allow.
+#[derive(Debug, PartialEq)]
+pub struct TagMismatch {
+    pub tag: u64,
+    /// Offset of the tag header.
+    pub offset: usize,
+    pub expected: &'static str,
+    pub found: &'static str,
+}
+
+/// A header whose argument (integer value, length or tag number) is not in its shortest form,
+/// as canonical CBOR requires.
+#[derive(Debug, PartialEq)]
+pub struct NonMinimal {
+    /// Offset of the header.
+    pub offset: usize,
+    pub major_type: &'static str,
+    pub value: u64,
+}
+
+/// The major type a registered tag must wrap, for the tags where RFC 8949 restricts it.
+fn expected_major_type(tag: u64) -> Option<u8> {
+    match tag {
+        0 | 32 | 33 | 34 => Some(3),
+        2 | 3 | 21 | 22 | 23 | 37 => Some(2),
+        _ => None,
+    }
+}
+
+pub(crate) fn major_type_name(major_type: u8) -> &'static str {
+    match major_type {
+        0 => "unsigned integer",
+        1 => "negative integer",
+        2 => "byte string",
+        3 => "text string",
+        4 => "array",
+        5 => "map",
+        6 => "tag",
+        _ => "simple/float",
+    }
+}
+
+/// Checks every tag in `bytes` against the major type its definition requires,
+/// returning the tags that wrap something else. An empty result means all tags are valid.
+pub fn validate_tag_content(bytes: &[u8]) -> Result<Vec<TagMismatch>, DecodeError> {
+    let mut mismatches = Vec::new();
+    walk_all(bytes, &mut |head, content| {
+        if head.major_type != 6 {
+            return;
+        }
+        let found = content >> 5;
+        if let Some(expected) = expected_major_type(head.argument).filter(|&expected| expected != found) {
+            mismatches.push(TagMismatch {
+                tag: head.argument,
+                offset: head.offset,
+                expected: major_type_name(expected),
+                found: major_type_name(found),
+            });
+        }
+    })?;
+    Ok(mismatches)
+}
+
+/// Returns every integer, length and tag number in `bytes` that is encoded in more bytes than needed.
+/// An empty result means all arguments are minimal.
+pub fn validate_canonical(bytes: &[u8]) -> Result<Vec<NonMinimal>, DecodeError> {
+    let mut violations = Vec::new();
+    walk_all(bytes, &mut |head, _| {
+        if head.major_type != 7 && head.additional < 28 && head.additional != minimal_additional(head.argument) {
+            violations.push(NonMinimal {
+                offset: head.offset,
+                major_type: major_type_name(head.major_type),
+                value: head.argument,
+            });
+        }
+    })?;
+    Ok(violations)
+}
+
+/// The additional information value of the shortest encoding of `argument`.
+pub(crate) fn minimal_additional(argument: u64) -> u8 {
+    match argument {
+        0..=23 => argument as u8,
+        24..=0xff => 24,
+        0x100..=0xffff => 25,
+        0x10000..=0xffff_ffff => 26,
+        _ => 27,
+    }
+}
+
+/// A data item header as found in the input.
+pub(crate) struct Head {
+    pub offset: usize,
+    pub major_type: u8,
+    pub additional: u8,
+    /// The value, length or tag number; 0 for indefinite lengths and for major type 7.
+    pub argument: u64,
+}
+
+/// Walks all top-level items, see [`walk`].
+pub(crate) fn walk_all(bytes: &[u8], visit: &mut impl FnMut(&Head, u8)) -> Result<(), DecodeError> {
+    let mut idx = 0;
+    while idx < bytes.len() {
+        walk(bytes, &mut idx, visit)?;
+    }
+    Ok(())
+}
+
+/// Calls `visit` for the header of the item at `idx` and of everything nested in it, in document order.
+/// Besides the header, `visit` gets the byte after it, or 0 at the end of the input.
+fn walk(bytes: &[u8], idx: &mut usize, visit: &mut impl FnMut(&Head, u8)) -> Result<(), DecodeError> {
+    let offset = *idx;
+    let initial = *bytes.get(offset).ok_or(DecodeError::Truncated { offset })?;
+    let major_type = initial >> 5;
+    let additional = initial & 0b00011111;
+    if major_type == 7 {
+        visit(&Head { offset, major_type, additional, argument: 0 }, 0);
+        decode_at(bytes, idx)?;
+        return Ok(());
+    }
+    let indefinite = additional == 31 && (2..=5).contains(&major_type);
+    let argument = if indefinite {
+        *idx += 1;
+        0
+    } else {
+        get_int(bytes, idx)?
+    };
+    visit(&Head { offset, major_type, additional, argument }, bytes.get(*idx).copied().unwrap_or(0));
+
+    if indefinite {
+        while !at_break(bytes, idx)? {
+            walk(bytes, idx, visit)?;
+        }
+        return Ok(());
+    }
+    match major_type {
+        2 | 3 => {
+            let len = usize::try_from(argument).map_err(|_| DecodeError::LengthTooLarge { offset })?;
+            take(bytes, idx, len)?;
+        }
+        4 | 5 => {
+            let items = if major_type == 5 { argument.saturating_mul(2) } else { argument };
+            for _ in 0..items {
+                walk(bytes, idx, visit)?;
+            }
+        }
+        6 => walk(bytes, idx, visit)?,
+        _ => {}
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn date_time_wrapping_integer() {
+        assert_eq!(Ok(vec![TagMismatch { tag: 0, offset: 1, expected: "text string", found: "unsigned integer" }]),
+                   validate_tag_content(&[0x82, 0xc0, 0x01, 0xc0, 0x61, b'x']));
+    }
+
+    #[test]
+    fn valid_tags() {
+        assert_eq!(Ok(vec![]), validate_tag_content(&[0xd8, 0x20, 0x61, b'x', 0xc2, 0x41, 0x01, 0xc1, 0x01]));
+    }
+
+    #[test]
+    fn array_length_non_minimal() {
+        assert_eq!(Ok(vec![NonMinimal { offset: 0, major_type: "array", value: 2 }]),
+                   validate_canonical(&[0x98, 0x02, 0x01, 0x02]));
+    }
+
+    #[test]
+    fn non_minimal_in_every_major_type() {
+        // 0x18 0x01, [h'' with 2-byte length, "a" with 1-byte length], {[]: 6(-1)} with 4-byte lengths
+        let bytes = [0x18, 0x01, 0x82, 0x59, 0x00, 0x00, 0x78, 0x01, b'a',
+            0xba, 0x00, 0x00, 0x00, 0x01, 0x80, 0xd8, 0x06, 0x38, 0x00];
+        assert_eq!(Ok(vec![
+            NonMinimal { offset: 0, major_type: "unsigned integer", value: 1 },
+            NonMinimal { offset: 3, major_type: "byte string", value: 0 },
+            NonMinimal { offset: 6, major_type: "text string", value: 1 },
+            NonMinimal { offset: 9, major_type: "map", value: 1 },
+            NonMinimal { offset: 15, major_type: "tag", value: 6 },
+            NonMinimal { offset: 17, major_type: "negative integer", value: 0 },
+        ]), validate_canonical(&bytes));
+    }
+
+    #[test]
+    fn minimal_lengths() {
+        assert_eq!(Ok(vec![]), validate_canonical(&[0x98, 0x18, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01,
+            0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01,
+            0x9f, 0xff, 0xfb, 0, 0, 0, 0, 0, 0, 0, 0]));
+    }
+}
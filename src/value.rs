@@ -0,0 +1,70 @@
+use crate::MajorType::{self, *};
+
+/// A decoded item without the wire-level details of [`MajorType`]:
+/// integers share one variant regardless of sign, and floats regardless of width.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Int(i128),
+    Float(f64),
+    Text(String),
+    Bytes(Vec<u8>),
+    Array(Vec<Value>),
+    Map(Vec<(Value, Value)>),
+    Bool(bool),
+    Null,
+    Undefined,
+    Simple(u8),
+    Tag(u64, Box<Value>),
+}
+
+impl MajorType {
+    /// Converts this item to a [`Value`], or `None` if it contains an `Invalid` item.
+    pub fn to_value(&self) -> Option<Value> {
+        Some(match self {
+            U(v) => Value::Int(*v as i128),
+            N(v) => Value::Int(*v),
+            BStr(bytes) => Value::Bytes(bytes.clone()),
+            Str(text) => Value::Text(text.to_string()),
+            Arr(items) => Value::Array(items.iter().map(MajorType::to_value).collect::<Option<_>>()?),
+            Map(entries) => Value::Map(entries.iter()
+                .map(|(key, value)| Some((key.to_value()?, value.to_value()?)))
+                .collect::<Option<_>>()?),
+            Tag(number, content) => Value::Tag(*number, Box::new(content.to_value()?)),
+            False => Value::Bool(false),
+            True => Value::Bool(true),
+            Null => Value::Null,
+            Undefined => Value::Undefined,
+            Simple(v) => Value::Simple(*v),
+            F16(v) | F32(v) => Value::Float(*v as f64),
+            F64(v) => Value::Float(*v),
+            Invalid => return None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::decode_at_offset;
+
+    #[test]
+    fn mixed_tree() {
+        // [1, -2, "a", h'01', [true, null], 1(1.5), undefined]
+        let bytes = [0x87, 0x01, 0x21, 0x61, b'a', 0x41, 0x01, 0x82, 0xf5, 0xf6, 0xc1, 0xf9, 0x3e, 0x00, 0xf7];
+        let (item, _) = decode_at_offset(&bytes, 0).unwrap();
+        assert_eq!(Some(Value::Array(vec![
+            Value::Int(1),
+            Value::Int(-2),
+            Value::Text("a".into()),
+            Value::Bytes(vec![1]),
+            Value::Array(vec![Value::Bool(true), Value::Null]),
+            Value::Tag(1, Box::new(Value::Float(1.5))),
+            Value::Undefined,
+        ])), item.to_value());
+    }
+
+    #[test]
+    fn invalid_has_no_value() {
+        assert_eq!(None, Arr(vec![U(1), Invalid]).to_value());
+    }
+}
@@ -0,0 +1,56 @@
+use crate::validate::validate_canonical;
+use crate::{decode_item, DecodeError, MajorType, State};
+
+/// Something questionable that did not stop decoding, found in the item header at `offset`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Warning {
+    pub offset: usize,
+    pub kind: WarningKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WarningKind {
+    /// The integer, length or tag number `value` is encoded in more bytes than needed.
+    NonMinimal { major_type: &'static str, value: u64 },
+    /// The text string is not valid UTF-8 from `position` bytes into its content on, and was
+    /// decoded with U+FFFD replacement characters.
+    LossyUtf8 { position: usize },
+}
+
+/// Decodes all top-level items, reading invalid UTF-8 lossily instead of failing on it, and returns
+/// warnings for that and for arguments that are not in their shortest form, ordered by offset.
+/// Input that is malformed otherwise still fails.
+pub fn decode_verbose(bytes: &[u8]) -> Result<(Vec<MajorType>, Vec<Warning>), DecodeError> {
+    let mut warnings = Vec::new();
+    let mut state = State { warnings: Some(&mut warnings), ..Default::default() };
+    let mut items = Vec::new();
+    let mut idx = 0;
+    while idx < bytes.len() {
+        items.push(decode_item(bytes, &mut idx, &mut state)?);
+    }
+    for violation in validate_canonical(bytes)? {
+        let kind = WarningKind::NonMinimal { major_type: violation.major_type, value: violation.value };
+        warnings.push(Warning { offset: violation.offset, kind });
+    }
+    warnings.sort_by_key(|warning| warning.offset);
+    Ok((items, warnings))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::MajorType::*;
+
+    #[test]
+    fn non_minimal_and_lossy() {
+        // [24 encoded in two bytes as 0x19 0x0018, "a\xffb"]
+        let bytes = [0x82, 0x19, 0x00, 0x18, 0x63, b'a', 0xff, b'b'];
+        let (items, warnings) = decode_verbose(&bytes).unwrap();
+        assert_eq!(vec![Arr(vec![U(24), Str("a\u{fffd}b".into())])], items);
+        assert_eq!(vec![
+            Warning { offset: 1, kind: WarningKind::NonMinimal { major_type: "unsigned integer", value: 24 } },
+            Warning { offset: 4, kind: WarningKind::LossyUtf8 { position: 1 } },
+        ], warnings);
+        assert_eq!(Err(DecodeError::Truncated { offset: 1 }), decode_verbose(&[0x81]));
+    }
+}
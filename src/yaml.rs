@@ -0,0 +1,147 @@
+use std::fmt::Write;
+
+use crate::MajorType::{self, *};
+use crate::diag::write_text;
+use crate::{base64, decode_items};
+
+/// Renders each top-level item as a YAML document in block style.
+///
+/// Text is always double-quoted. Byte strings become `!!binary` base64 blocks, and tags, undefined,
+/// simple values and invalid items get local YAML tags: `!cbor/1 0`, `!cbor/undefined null`,
+/// `!cbor/simple 16`, `!cbor/invalid null`. A YAML node carries only one tag, so nested CBOR tags
+/// are joined as `!cbor/24/55799` and a tagged byte string is a plain base64 block.
+/// Map keys other than integers and text are written as the text of their diagnostic notation.
+pub fn decode_to_yaml(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    for (i, item) in decode_items(bytes).iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        out.push_str("---");
+        write_node(item, 0, &mut out);
+    }
+    out
+}
+
+/// Writes `item` after a `---`, `-` or `key:` that has just been written.
+/// The entries of a collection go on new lines at `depth` levels of indentation.
+fn write_node(item: &MajorType, depth: usize, out: &mut String) {
+    match item {
+        Tag(..) => {
+            out.push_str(" !cbor");
+            let mut content = item;
+            while let Tag(number, inner) = content {
+                write!(out, "/{}", number).unwrap();
+                content = inner;
+            }
+            match content {
+                BStr(bytes) if bytes.is_empty() => out.push_str(" \"\""),
+                BStr(bytes) => write_base64(bytes, depth, out),
+                content => write_node(content, depth, out),
+            }
+        }
+        BStr(bytes) if !bytes.is_empty() => {
+            out.push_str(" !!binary");
+            write_base64(bytes, depth, out);
+        }
+        Arr(items) if !items.is_empty() => {
+            for item in items {
+                new_line(depth, out);
+                out.push('-');
+                write_node(item, depth + 1, out);
+            }
+        }
+        Map(entries) if !entries.is_empty() => {
+            for (key, value) in entries {
+                new_line(depth, out);
+                match key {
+                    U(v) => write!(out, "{}", v).unwrap(),
+                    N(v) => write!(out, "{}", v).unwrap(),
+                    Str(text) => write_text(text, out).unwrap(),
+                    key => write_text(&key.to_string(), out).unwrap(),
+                }
+                out.push(':');
+                write_node(value, depth + 1, out);
+            }
+        }
+        scalar => {
+            out.push(' ');
+            write_scalar(scalar, out);
+        }
+    }
+}
+
+fn write_scalar(item: &MajorType, out: &mut String) {
+    match item {
+        U(v) => write!(out, "{}", v).unwrap(),
+        N(v) => write!(out, "{}", v).unwrap(),
+        BStr(_) => out.push_str("!!binary \"\""),
+        Str(text) => write_text(text, out).unwrap(),
+        Arr(_) => out.push_str("[]"),
+        Map(_) => out.push_str("{}"),
+        False => out.push_str("false"),
+        True => out.push_str("true"),
+        Null => out.push_str("null"),
+        Undefined => out.push_str("!cbor/undefined null"),
+        Simple(v) => write!(out, "!cbor/simple {}", v).unwrap(),
+        F16(v) | F32(v) => write_number(*v as f64, out),
+        F64(v) => write_number(*v, out),
+        Tag(..) | Invalid => out.push_str("!cbor/invalid null"),
+    }
+}
+
+fn write_number(v: f64, out: &mut String) {
+    if v.is_nan() {
+        out.push_str(".nan");
+    } else if v.is_infinite() {
+        out.push_str(if v > 0.0 { ".inf" } else { "-.inf" });
+    } else {
+        write!(out, "{:?}", v).unwrap();
+    }
+}
+
+/// Writes a literal block with the base64 of `bytes` in lines of 76 characters.
+fn write_base64(bytes: &[u8], depth: usize, out: &mut String) {
+    out.push_str(" |");
+    let encoded = base64::encode_standard(bytes);
+    // base64 is ASCII, so every chunk is a valid string
+    for line in encoded.as_bytes().chunks(76) {
+        new_line(depth, out);
+        out.push_str(std::str::from_utf8(line).unwrap());
+    }
+}
+
+fn new_line(depth: usize, out: &mut String) {
+    out.push('\n');
+    out.extend(std::iter::repeat_n(' ', 2 * depth));
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn nested_document() {
+        // {"a": [1, h'010203', []], "b": {-1: 1(0)}, "c": 24(h'f6')}, "x"
+        let bytes = [0xa3, 0x61, b'a', 0x83, 0x01, 0x43, 0x01, 0x02, 0x03, 0x80, 0x61, b'b', 0xa1, 0x20, 0xc1, 0x00,
+            0x61, b'c', 0xd8, 0x18, 0x41, 0xf6, 0x61, b'x'];
+        assert_eq!("---
+\"a\":
+  - 1
+  - !!binary |
+    AQID
+  - []
+\"b\":
+  -1: !cbor/1 0
+\"c\": !cbor/24 |
+  9g==
+--- \"x\"", decode_to_yaml(&bytes));
+    }
+
+    #[test]
+    fn special_scalars() {
+        // [undefined, simple(16), NaN, -Infinity, h'']
+        let bytes = [0x85, 0xf7, 0xf0, 0xf9, 0x7e, 0x00, 0xf9, 0xfc, 0x00, 0x40];
+        assert_eq!("---\n- !cbor/undefined null\n- !cbor/simple 16\n- .nan\n- -.inf\n- !!binary \"\"", decode_to_yaml(&bytes));
+    }
+}